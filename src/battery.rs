@@ -0,0 +1,126 @@
+use std::rc::Rc;
+
+use crate::{
+    labels::{Label, Labels},
+    Info, InfoResult,
+};
+
+/// The charge of the first battery and whether it is charging.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Battery {
+    pub percent: u8,
+    pub charging: bool,
+}
+
+/// Shows the battery charge, e.g. `Battery: 85% (charging)`.
+pub struct BatteryInfo {
+    labels: Rc<Labels>,
+}
+
+impl BatteryInfo {
+    pub fn new(labels: Rc<Labels>) -> Self {
+        Self { labels }
+    }
+
+    fn format(&self, separator: &str) -> String {
+        match read() {
+            Some(battery) => format!(
+                "{}{}{}{}",
+                self.labels.get(Label::Battery),
+                separator,
+                self.labels.percent(battery.percent, 0),
+                if battery.charging {
+                    format!(" ({})", self.labels.get(Label::Charging))
+                } else {
+                    String::new()
+                }
+            ),
+            None => format!("{}{}--", self.labels.get(Label::Battery), separator),
+        }
+    }
+}
+
+impl Info for BatteryInfo {
+    fn name(&self) -> &str {
+        "battery"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        Ok(self.format(": "))
+    }
+
+    fn get_info_compact(&mut self) -> InfoResult {
+        Ok(self.format(" "))
+    }
+}
+
+/// Reads the first battery from `/sys/class/power_supply`.
+#[cfg(target_os = "linux")]
+pub fn read() -> Option<Battery> {
+    let mut supplies: Vec<_> = std::fs::read_dir("/sys/class/power_supply")
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    supplies.sort();
+    supplies.into_iter().find_map(|supply| {
+        let read = |name: &str| std::fs::read_to_string(supply.join(name)).ok();
+        if read("type")?.trim() != "Battery" {
+            return None;
+        }
+        Some(Battery {
+            percent: read("capacity")?.trim().parse::<u8>().ok()?.min(100),
+            charging: read("status").is_some_and(|status| status.trim() == "Charging"),
+        })
+    })
+}
+
+#[cfg(windows)]
+pub fn read() -> Option<Battery> {
+    #[repr(C)]
+    #[allow(dead_code)] // filled in by the call, only some fields are used
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+
+    const NO_BATTERY: u8 = 128;
+    const UNKNOWN: u8 = 255;
+    const CHARGING: u8 = 8;
+
+    let mut status = SystemPowerStatus {
+        ac_line_status: 0,
+        battery_flag: 0,
+        battery_life_percent: 0,
+        system_status_flag: 0,
+        battery_life_time: 0,
+        battery_full_life_time: 0,
+    };
+    // SAFETY: `status` is a SYSTEM_POWER_STATUS the call fills in
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    if status.battery_flag == UNKNOWN
+        || status.battery_flag & NO_BATTERY != 0
+        || status.battery_life_percent == UNKNOWN
+    {
+        return None;
+    }
+    Some(Battery {
+        percent: status.battery_life_percent.min(100),
+        charging: status.battery_flag & CHARGING != 0,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn read() -> Option<Battery> {
+    None
+}
@@ -0,0 +1,619 @@
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::{
+    disk::DiskSelection,
+    format::DurationFormat,
+    http::HttpSource,
+    labels::LabelText,
+    params::{Level, Param},
+};
+
+#[derive(Parser, Debug, Clone)]
+#[command(version, about, args_override_self = true)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Read options from this file of `key = value` lines, e.g. `interval = 5`;
+    /// options on the command line win. On unix, SIGHUP reads it again
+    #[arg(long, value_name = "PATH", global = true)]
+    pub config: Option<std::path::PathBuf>,
+
+    /// List every provider with its default state and exit
+    #[arg(long)]
+    pub list_providers: bool,
+
+    /// Send this text to the chatbox once and exit, to test the OSC connection
+    #[arg(long, value_name = "TEXT", hide = true)]
+    pub test_send: Option<String>,
+
+    /// Send the messages in a file written by `--record` again, at the pace
+    /// they were recorded, and exit
+    #[arg(long, value_name = "PATH")]
+    pub replay: Option<std::path::PathBuf>,
+
+    /// Only show these, e.g. `--show time,cpu,uptime`; when absent the default
+    /// providers are shown unless turned off by their `--no-*` flag
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "PROVIDERS",
+        global = true
+    )]
+    pub show: Option<Vec<ProviderKind>>,
+
+    /// Do not show time
+    #[arg(short = 't', long, global = true)]
+    pub no_time: bool,
+
+    /// Do not show cpu usage
+    #[arg(short = 'c', long, global = true)]
+    pub no_cpu: bool,
+
+    /// Do not show ram usage
+    #[arg(short = 'r', long, global = true)]
+    pub no_ram: bool,
+
+    /// Do not show gpu usage
+    #[arg(short = 'g', long, global = true)]
+    pub no_gpu: bool,
+
+    /// Fail instead of turning gpu usage off when no supported gpu is found
+    #[arg(long, conflicts_with = "no_gpu", global = true)]
+    pub gpu: bool,
+
+    /// Do not show the battery
+    #[arg(long, global = true)]
+    pub no_battery: bool,
+
+    /// Show the battery even when none was found at startup
+    #[arg(long, conflicts_with = "no_battery", global = true)]
+    pub battery: bool,
+
+    /// Show the cpu temperature, read from LibreHardwareMonitor (Windows only,
+    /// while it is running) or sysinfo; `auto` prefers LibreHardwareMonitor
+    #[arg(long, value_enum, value_name = "SENSOR", global = true)]
+    pub cpu_sensor: Option<CpuSensor>,
+
+    /// Split cpu usage into user and system time where the OS reports them
+    /// (Linux), e.g. `usr 40% sys 10%`
+    #[arg(long, global = true)]
+    pub cpu_breakdown: bool,
+
+    /// Measure cpu usage this many times spread over each interval and show
+    /// the average, for a steadier reading
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..), global = true)]
+    pub cpu_samples: u32,
+
+    /// The unit temperatures are shown in; `--level` thresholds of
+    /// temperatures are given in it too
+    #[arg(long, value_enum, value_name = "UNIT", default_value_t = TempUnit::Celsius, global = true)]
+    pub temp_unit: TempUnit,
+
+    /// Show how much cpu and ram usage changed since the last message
+    #[arg(long, global = true)]
+    pub show_delta: bool,
+
+    /// Show ram usage as a bar instead, e.g. `RAM: [████░░░░░░] 38%`
+    #[arg(long, global = true)]
+    pub ram_bar: bool,
+
+    /// How many characters `--ram-bar` and `--vram-bar` are wide, not counting
+    /// the brackets
+    #[arg(long, value_name = "WIDTH", default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..=50), global = true)]
+    pub bar_width: u64,
+
+    /// How to show ram usage
+    #[arg(long, value_enum, default_value_t = RamFormat::Both, global = true)]
+    pub ram_format: RamFormat,
+
+    /// How to count used ram; `auto` is `available` on macOS and `used` elsewhere
+    #[arg(long, value_enum, default_value_t = RamMode::Auto, global = true)]
+    pub ram_mode: RamMode,
+
+    /// Monitor the first gpu whose name contains this, see `list-gpus`
+    #[arg(long, value_name = "NAME", global = true)]
+    pub gpu_name: Option<String>,
+
+    /// Show the gpu video engine clock, if supported
+    #[arg(long, global = true)]
+    pub gpu_video_clock: bool,
+
+    /// Show the gpu performance state, from P0 (fastest) to P15 (idle)
+    #[arg(long, global = true)]
+    pub gpu_pstate: bool,
+
+    /// Show the energy the gpu used since launch, e.g. `0.42Wh`, and print it
+    /// on exit (on cards that count it)
+    #[arg(long, global = true)]
+    pub gpu_energy: bool,
+
+    /// Show how busy the gpu's memory controller is, e.g. `MemUtil: 45%`
+    #[arg(long, global = true)]
+    pub gpu_mem_util: bool,
+
+    /// Show vram usage as a bar, e.g. `[██████░░░░] 61%`
+    #[arg(long, global = true)]
+    pub vram_bar: bool,
+
+    /// Only show the gpu's used and total vram
+    #[arg(long, global = true)]
+    pub gpu_vram_only: bool,
+
+    /// Show whether the gpu temperature rose (↑), fell (↓) or held (→)
+    /// since the last message
+    #[arg(long, global = true)]
+    pub gpu_temp_trend: bool,
+
+    /// Keep showing a provider's last good value for this many failed reads
+    /// before showing N/A
+    #[arg(long, value_name = "TICKS", default_value_t = 3, global = true)]
+    pub stale_ticks: u32,
+
+    /// Mark values kept from an earlier read with `~`
+    #[arg(long, global = true)]
+    pub stale_marker: bool,
+
+    /// How to write the uptime: `human` (3h 42m), `clock` (3:42:10) or a
+    /// pattern using %D, %H, %h, %M, %m, %S and %s
+    #[arg(long, value_name = "FORMAT", default_value = "human", global = true)]
+    pub uptime_format: DurationFormat,
+
+    /// Show the used space of the disk mounted here; `all` shows every disk
+    /// and `total` adds them up; can be repeated
+    #[arg(long, value_name = "MOUNT", global = true)]
+    pub disk: Vec<DiskSelection>,
+
+    /// Show how much ram this program uses, like `--show` with `self`
+    #[arg(long, global = true)]
+    pub self_usage: bool,
+
+    /// Show whether you are at the keyboard (Windows only), like `--show`
+    /// with `idle`
+    #[arg(long, global = true)]
+    pub idle: bool,
+
+    /// Seconds without keyboard or mouse input after which you count as idle
+    #[arg(long, value_name = "SECS", default_value_t = 300, global = true)]
+    pub idle_after: u64,
+
+    /// Show the combined cpu and ram usage of processes whose name contains this
+    #[arg(short = 'w', long, value_name = "NAME", global = true)]
+    pub watch_process: Option<String>,
+
+    /// Shorten process names in the chatbox to this many characters
+    #[arg(long, value_name = "CHARS", default_value_t = 24, value_parser = clap::value_parser!(u64).range(1..), global = true)]
+    pub max_process_name: u64,
+
+    /// Show a value from a JSON http endpoint, polled on its own schedule, e.g.
+    /// `url=http://host/api,path=$.main.temp,label=Weather[,interval=60]`
+    #[arg(long, value_name = "SOURCE", global = true)]
+    pub http_source: Vec<HttpSource>,
+
+    /// Run every executable in this directory each message and show what it
+    /// prints, one line per script in file name order
+    #[arg(long, value_name = "PATH", global = true)]
+    pub plugin_dir: Option<std::path::PathBuf>,
+
+    /// Leave out a plugin's line when it runs longer than this many milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 1000, global = true)]
+    pub plugin_timeout_ms: u64,
+
+    /// Add a line that rotates through these each message, e.g. `gaming,afk,brb`
+    #[arg(long, value_name = "QUIPS", value_delimiter = ',', global = true)]
+    pub quips: Vec<String>,
+
+    /// Show the FPS that VRChat sends to this OSC address
+    #[arg(long, value_name = "ADDRESS", global = true)]
+    pub fps_address: Option<String>,
+
+    /// Time interval in seconds
+    #[arg(short, long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..), global = true)]
+    pub interval: u64,
+
+    /// Language of the labels and the date
+    #[arg(long, value_enum, default_value_t = Lang::En, global = true)]
+    pub lang: Lang,
+
+    /// Send each line as its own chatbox message, in order and spaced out to
+    /// stay under the chatbox rate limit
+    #[arg(long, global = true)]
+    pub split_messages: bool,
+
+    /// Send this to the chatbox when every provider failed or nothing is shown
+    #[arg(long, value_name = "TEXT", global = true)]
+    pub fallback_text: Option<String>,
+
+    /// Rename a label, e.g. `--label cpu=Proc`; the labels are cpu, processes,
+    /// ram, gpu, video, pstate, mem-util, vram, disk, uptime, fps, self-usage,
+    /// active, idle, battery and charging
+    #[arg(long, value_name = "LABEL=TEXT", global = true)]
+    pub label: Vec<LabelText>,
+
+    /// Join all lines into one, with this separator (` | ` if none is given),
+    /// e.g. `CPU: 5.00%, Processes: 339 | RAM: 12.3 GiB (38.00%)`
+    #[arg(
+        long,
+        value_name = "SEPARATOR",
+        num_args = 0..=1,
+        default_missing_value = " | ",
+        conflicts_with = "split_messages",
+        global = true
+    )]
+    pub single_line: Option<String>,
+
+    /// Put every provider on one abbreviated line, e.g.
+    /// `12:34 | CPU5% RAM38% GPU80% 65C`, to fit the chatbox's 144 characters
+    #[arg(long, global = true)]
+    pub compact: bool,
+
+    /// Drop trailing zeros from the numbers the providers show, e.g. `5.00%`
+    /// becomes `5%` and `38.40%` becomes `38.4%`; text like quips is kept
+    #[arg(long, global = true)]
+    pub trim_zeros: bool,
+
+    /// Drop the units of the numbers the providers show, e.g. `%`, `W`, `°C`,
+    /// `°F` and `MHz`, keeping the ones of sizes like `GiB`; text like quips
+    /// is kept
+    #[arg(long, global = true)]
+    pub no_units: bool,
+
+    /// Replace non-ascii symbols like `°` with ascii equivalents
+    #[arg(long, global = true)]
+    pub ascii: bool,
+
+    /// Append every message to this file with the time it was sent, to send
+    /// them again later with `--replay`
+    #[arg(long, value_name = "PATH", global = true)]
+    pub record: Option<std::path::PathBuf>,
+
+    /// Print the messages instead of sending them, e.g. to only `--record` them
+    #[arg(long, global = true)]
+    pub print_only: bool,
+
+    /// Send the message to the chatbox, or the `--param` values to the avatar
+    #[arg(long, value_enum, default_value_t = Output::Chatbox, global = true)]
+    pub output: Output,
+
+    /// Send a metric to an avatar parameter in `parameters` output, e.g.
+    /// `CpuUsage:float:/avatar/parameters/CPU`; the type is int, bool or
+    /// float, which only takes percentages
+    #[arg(long, value_name = "PARAM", global = true)]
+    pub param: Vec<Param>,
+
+    /// Send the level of a metric as an int in `parameters` output: 0 below
+    /// warn, 1 below crit and 2 above, e.g. `CpuUsage:70:90:/avatar/parameters/CpuLevel`
+    #[arg(long, value_name = "LEVEL", global = true)]
+    pub level: Vec<Level>,
+
+    /// Send messages straight to the chatbox, or open the keyboard with them
+    #[arg(long, value_enum, default_value_t = ChatboxMode::Immediate, global = true)]
+    pub chatbox_mode: ChatboxMode,
+
+    /// The arguments of each chatbox message, in order; the first must be `text`
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "text,send",
+        global = true
+    )]
+    pub chatbox_args: Vec<ChatboxArg>,
+
+    /// Host of the OSC server
+    #[arg(long, default_value = "127.0.0.1", global = true)]
+    pub osc_host: String,
+
+    /// Port of the OSC server
+    #[arg(long, default_value_t = 9000, global = true)]
+    pub osc_port: u16,
+
+    /// How to reach the OSC server; `tcp` frames each packet with SLIP
+    #[arg(long, value_enum, value_name = "TRANSPORT", default_value_t = OscTransport::Udp, global = true)]
+    pub osc_transport: OscTransport,
+
+    /// Send udp from this local address, to pick the network interface on a
+    /// machine with several
+    #[arg(long, value_name = "IP", global = true)]
+    pub bind_source: Option<std::net::IpAddr>,
+
+    /// Warn once if nothing seems to be listening for the messages
+    #[arg(long, global = true)]
+    pub warn_if_no_listener: bool,
+
+    /// Send to this unix datagram socket instead of the OSC server's udp port
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH", global = true)]
+    pub osc_unix: Option<std::path::PathBuf>,
+
+    /// Leave at least this many milliseconds between any two sends, holding
+    /// back split messages, retries and control socket requests alike
+    #[arg(long, value_name = "MS", default_value_t = 0, global = true)]
+    pub send_min_gap_ms: u64,
+
+    /// Try a failed send this many more times before giving up on the message
+    #[arg(long, value_name = "N", default_value_t = 3, global = true)]
+    pub send_retries: u32,
+
+    /// Wait this long before the first retry, doubling for each one after it
+    /// up to 5 seconds
+    #[arg(long, value_name = "MS", default_value_t = 200, global = true)]
+    pub send_backoff_ms: u64,
+
+    /// Exit with an error after this many messages in a row failed to send,
+    /// each after its retries; 0 never exits
+    #[arg(long, value_name = "N", default_value_t = 0, global = true)]
+    pub max_failures: u32,
+
+    /// Listen on this unix socket, or named pipe like `\\.\pipe\sysinfo` on
+    /// Windows, for `send` (send the stats now), `say TEXT` (send TEXT now)
+    /// and `enable`/`disable PROVIDER`, one command per line
+    #[cfg(any(unix, windows))]
+    #[arg(long, value_name = "PATH", global = true)]
+    pub control_socket: Option<std::path::PathBuf>,
+
+    /// Add a random delay of up to this many milliseconds to each interval
+    #[arg(long, value_name = "MS", default_value_t = 0, global = true)]
+    pub jitter: u64,
+
+    /// Print the peak cpu usage, gpu temperature and ram usage since launch
+    /// on exit
+    #[arg(long, global = true)]
+    pub track_peaks: bool,
+
+    /// Print how long each provider and each tick took to stderr
+    #[arg(long, global = true)]
+    pub debug_timing: bool,
+
+    /// Providers turned on or off over the control socket, over everything else
+    #[arg(skip)]
+    pub toggled: Vec<(ProviderKind, bool)>,
+}
+
+impl Args {
+    /// Turns a provider on or off no matter what `--show` and `--no-*` say.
+    pub fn set_enabled(&mut self, kind: ProviderKind, on: bool) {
+        self.toggled.retain(|(k, _)| *k != kind);
+        self.toggled.push((kind, on));
+    }
+
+    /// Whether the provider is allowed by `--show` and not disabled by `--no-*`,
+    /// unless `set_enabled` says otherwise.
+    pub fn enabled(&self, kind: ProviderKind) -> bool {
+        if let Some((_, on)) = self.toggled.iter().find(|(k, _)| *k == kind) {
+            return *on;
+        }
+        let disabled = match kind {
+            ProviderKind::Time => self.no_time,
+            ProviderKind::Cpu => self.no_cpu,
+            ProviderKind::Ram => self.no_ram,
+            ProviderKind::Gpu => self.no_gpu,
+            ProviderKind::Battery => self.no_battery,
+            _ => false,
+        };
+        let shown = match &self.show {
+            Some(kinds) => kinds.contains(&kind),
+            None => kind.default_enabled(),
+        } || (kind == ProviderKind::SelfUsage && self.self_usage)
+            || (kind == ProviderKind::Idle && self.idle);
+        shown && !disabled
+    }
+
+    /// Whether a gpu was asked for by name, with `--gpu` or with `--show`, so
+    /// that not finding one is an error rather than turning it off.
+    pub fn gpu_expected(&self) -> bool {
+        self.gpu
+            || self.gpu_name.is_some()
+            || self
+                .show
+                .as_ref()
+                .is_some_and(|kinds| kinds.contains(&ProviderKind::Gpu))
+    }
+}
+
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Keep sending system information (the default)
+    Run,
+    /// Check that the OSC socket and every monitoring backend work
+    Check,
+    /// List the gpus that can be monitored
+    ListGpus,
+    /// Send system information once and exit
+    Once,
+    /// Start at login with the other options given here (a systemd user unit
+    /// on Linux, the registry's Run key on Windows)
+    Install,
+    /// Stop starting at login
+    Uninstall,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProviderKind {
+    /// Current date and time
+    Time,
+    /// Cpu usage and process count
+    Cpu,
+    /// Ram usage
+    Ram,
+    /// Gpu usage, power, temperature and vram
+    Gpu,
+    /// Used space of chosen disks
+    Disk,
+    /// Cpu and ram usage of chosen processes
+    Watch,
+    /// Values from JSON http endpoints
+    Http,
+    /// FPS reported by VRChat over OSC
+    Fps,
+    /// Time since the system booted
+    Uptime,
+    /// Ram used by this program
+    #[value(name = "self")]
+    SelfUsage,
+    /// Time since the last keyboard or mouse input
+    Idle,
+    /// Battery charge, by default only when a battery is found at startup
+    Battery,
+    /// Lines printed by the scripts in `--plugin-dir`
+    Plugins,
+    /// A rotating line of your own
+    Quips,
+}
+
+impl ProviderKind {
+    /// Whether the provider is shown when `--show` is absent.
+    pub fn default_enabled(self) -> bool {
+        !matches!(self, Self::Uptime | Self::SelfUsage | Self::Idle)
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpuSensor {
+    /// LibreHardwareMonitor if it is running, sysinfo otherwise
+    Auto,
+    /// LibreHardwareMonitor's WMI sensors
+    Lhm,
+    /// The temperature sensors found by sysinfo
+    Sysinfo,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// One chatbox message with every provider's line
+    Chatbox,
+    /// A bundle of avatar parameters, see `--param`
+    Parameters,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lang {
+    /// English, e.g. `04/17/2024`
+    En,
+    /// Japanese, e.g. `2024/04/17`
+    Ja,
+    /// German, e.g. `17.04.2024`
+    De,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChatboxMode {
+    /// Show the message in the chatbox right away
+    Immediate,
+    /// Open the keyboard with the message filled in, for editing
+    Keyboard,
+}
+
+/// One argument of `/chatbox/input`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChatboxArg {
+    /// The message
+    Text,
+    /// Whether to send right away, from `--chatbox-mode`
+    Send,
+    /// Play the notification sound
+    Sfx,
+    /// Don't play the notification sound
+    NoSfx,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OscTransport {
+    /// One datagram per packet, what VRChat expects
+    Udp,
+    /// A tcp connection to OSC relays and other software that prefer it
+    Tcp,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TempUnit {
+    /// Degrees Celsius
+    #[value(name = "C", alias = "celsius")]
+    Celsius,
+    /// Degrees Fahrenheit
+    #[value(name = "F", alias = "fahrenheit")]
+    Fahrenheit,
+}
+
+impl TempUnit {
+    /// The letter after the `°`.
+    pub fn symbol(self) -> char {
+        match self {
+            Self::Celsius => 'C',
+            Self::Fahrenheit => 'F',
+        }
+    }
+
+    /// Converts a sensor reading in °C to this unit.
+    pub fn convert(self, celsius: f32) -> f32 {
+        match self {
+            Self::Celsius => celsius,
+            Self::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    /// Converts a temperature in this unit to °C.
+    pub fn to_celsius(self, value: f32) -> f32 {
+        match self {
+            Self::Celsius => value,
+            Self::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RamFormat {
+    /// Percentage only, e.g. `RAM: 38.00%`
+    Pct,
+    /// Used memory only, e.g. `RAM: 12.3 GiB`
+    Abs,
+    /// Used and total memory, e.g. `RAM: 12.3 GiB/32.0 GiB`
+    AbsTotal,
+    /// Used memory and percentage, e.g. `RAM: 12.3 GiB (38.00%)`
+    Both,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RamMode {
+    /// Pick the mode that matches the OS's own task manager
+    Auto,
+    /// Memory reported as used by the OS
+    Used,
+    /// Total memory minus available memory
+    Available,
+}
+
+impl RamMode {
+    pub fn resolve(self) -> Self {
+        match self {
+            // used memory on macOS includes cache and compressed pages
+            Self::Auto if cfg!(target_os = "macos") => Self::Available,
+            Self::Auto => Self::Used,
+            mode => mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+    use crate::labels::Label;
+
+    #[test]
+    fn label_help_lists_every_label() {
+        let command = Args::command();
+        let help = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == "label")
+            .and_then(|arg| arg.get_help())
+            .expect("--label has a help text")
+            .to_string();
+        for label in Label::value_variants() {
+            let name = label.to_possible_value().unwrap().get_name().to_string();
+            assert!(help.contains(&name), "{} is missing from {:?}", name, help);
+        }
+    }
+}
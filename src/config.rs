@@ -0,0 +1,84 @@
+use std::{
+    env, fs,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use clap::{error::ErrorKind, Parser};
+
+use crate::cli::Args;
+
+static RELOAD: AtomicBool = AtomicBool::new(false);
+
+/// Reads `key = value` lines into the long options they stand for:
+/// `interval = 5` is `--interval 5`, `no-gpu = true` is `--no-gpu` and
+/// `label.cpu = "Proc"` is `--label cpu=Proc`. Keys may be repeated, and
+/// `#` starts a comment.
+fn read(path: &Path) -> Result<Vec<String>, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    let mut options = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!(
+                "{}:{}: expected `key = value`, got `{}`",
+                path.display(),
+                n + 1,
+                line
+            ));
+        };
+        let key = key.trim().replace('_', "-");
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        match (key.split_once('.'), value) {
+            (Some((key, name)), value) => {
+                options.push(format!("--{}", key));
+                options.push(format!("{}={}", name, value));
+            }
+            (None, "true") => options.push(format!("--{}", key)),
+            (None, "false") => {}
+            (None, value) => {
+                options.push(format!("--{}", key));
+                options.push(value.to_string());
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Parses the command line with the options from `path` put in front, so
+/// that the command line wins.
+pub fn parse(path: &Path) -> Result<Args, clap::Error> {
+    let options = read(path).map_err(|e| clap::Error::raw(ErrorKind::Io, e + "\n"))?;
+    let mut argv = env::args_os();
+    let bin = argv.next();
+    Args::try_parse_from(
+        bin.into_iter()
+            .chain(options.into_iter().map(Into::into))
+            .chain(argv),
+    )
+}
+
+/// Asks for the config file to be read again whenever SIGHUP arrives.
+#[cfg(unix)]
+pub fn reload_on_sighup() {
+    extern "C" fn on_sighup(_: libc::c_int) {
+        RELOAD.store(true, Ordering::SeqCst);
+    }
+    // SAFETY: the handler only stores to an atomic, which is signal safe
+    unsafe {
+        libc::signal(libc::SIGHUP, on_sighup as *const () as libc::sighandler_t);
+    }
+}
+
+/// Whether a reload was asked for since the last call.
+pub fn reload_requested() -> bool {
+    RELOAD.swap(false, Ordering::SeqCst)
+}
@@ -0,0 +1,512 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError},
+    },
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+};
+#[cfg(any(unix, windows))]
+use std::{
+    io,
+    path::Path,
+    sync::mpsc::{self, Sender},
+    thread,
+};
+
+use clap::ValueEnum;
+
+use crate::cli::ProviderKind;
+#[cfg(any(unix, windows))]
+use crate::workers::Workers;
+
+/// How long a connected client may stay quiet before it is dropped.
+#[cfg(any(unix, windows))]
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What an external program asked for over the control socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    /// Send the current stats right away
+    Send,
+    /// Send this text right away instead of the stats
+    Say(String),
+    /// Turn a provider on (`true`) or off and send right away
+    Toggle(ProviderKind, bool),
+}
+
+impl std::str::FromStr for Request {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (command, rest) = s.split_once(' ').unwrap_or((s, ""));
+        match command {
+            "send" if rest.is_empty() => Ok(Self::Send),
+            "say" if !rest.is_empty() => Ok(Self::Say(rest.to_string())),
+            "say" => Err("say needs a text".to_string()),
+            "enable" | "disable" => {
+                let kind = ProviderKind::from_str(rest.trim(), true)
+                    .map_err(|_| format!("unknown provider `{}`", rest.trim()))?;
+                Ok(Self::Toggle(kind, command == "enable"))
+            }
+            _ => Err(format!(
+                "unknown command `{}`, expected `send`, `say TEXT`, `enable PROVIDER` or `disable PROVIDER`",
+                s
+            )),
+        }
+    }
+}
+
+/// The requests from the control socket, coalesced as they are taken.
+pub struct Requests {
+    receiver: Receiver<Request>,
+    /// A toggle that came after the sends being coalesced
+    held: Option<Request>,
+}
+
+impl Requests {
+    pub fn new(receiver: Receiver<Request>) -> Self {
+        Self {
+            receiver,
+            held: None,
+        }
+    }
+
+    /// Waits up to `timeout` for the next request. Sends that piled up while
+    /// sends were held back are coalesced and the latest wins, but each
+    /// toggle is given on its own, in order.
+    fn next(&mut self, timeout: Duration) -> Result<Request, RecvTimeoutError> {
+        let mut latest = match self.held.take() {
+            Some(request) => request,
+            None => self.receiver.recv_timeout(timeout)?,
+        };
+        if matches!(latest, Request::Toggle(..)) {
+            return Ok(latest);
+        }
+        for request in self.receiver.try_iter() {
+            if matches!(request, Request::Toggle(..)) {
+                self.held = Some(request);
+                break;
+            }
+            latest = request;
+        }
+        Ok(latest)
+    }
+}
+
+/// Why `wait` returned.
+pub enum Wake {
+    Tick,
+    Request(Request),
+    Stop,
+}
+
+/// Waits for `duration`, waking up early for a request or when `running`
+/// is cleared.
+pub fn wait(running: &AtomicBool, duration: Duration, mut requests: Option<&mut Requests>) -> Wake {
+    let deadline = Instant::now() + duration;
+    while running.load(Ordering::SeqCst) {
+        let now = Instant::now();
+        if now >= deadline {
+            return Wake::Tick;
+        }
+        let slice = (deadline - now).min(Duration::from_millis(100));
+        match requests.as_deref_mut().map(|requests| requests.next(slice)) {
+            Some(Ok(request)) => return Wake::Request(request),
+            Some(Err(RecvTimeoutError::Timeout)) => {}
+            // the listener stopped, so only the deadline is left to wait for
+            Some(Err(RecvTimeoutError::Disconnected)) | None => std::thread::sleep(slice),
+        }
+    }
+    Wake::Stop
+}
+
+/// Listens on a unix socket for one command per line, answering each with
+/// `ok` or `error: ...`.
+#[cfg(unix)]
+pub fn listen(path: &Path, workers: &mut Workers) -> io::Result<Receiver<Request>> {
+    // a socket left behind by an earlier run would make bind fail
+    if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    // wake up now and then to notice the shutdown
+    listener.set_nonblocking(true)?;
+    let (sender, receiver) = mpsc::channel();
+    let running = workers.running();
+    let path = path.to_path_buf();
+    workers.spawn("control socket", move || {
+        while running.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(e) = serve(stream, &sender) {
+                        eprintln!("control socket: {}", e);
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => {
+                    eprintln!("control socket: {}", e);
+                    break;
+                }
+            }
+        }
+        let _ = fs::remove_file(&path);
+    });
+    Ok(receiver)
+}
+
+#[cfg(unix)]
+fn serve(stream: UnixStream, sender: &Sender<Request>) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            // a client that went quiet
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                break
+            }
+            Err(e) => return Err(e),
+        };
+        if let Some(answer) = answer(&line, sender) {
+            writeln!(writer, "{}", answer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Listens on a named pipe like `\\.\pipe\sysinfo` for one command per
+/// line, answering each with `ok` or `error: ...`.
+#[cfg(windows)]
+pub fn listen(path: &Path, workers: &mut Workers) -> io::Result<Receiver<Request>> {
+    let pipe = pipe::Pipe::create(path)?;
+    let (sender, receiver) = mpsc::channel();
+    let running = workers.running();
+    workers.spawn("control pipe", move || {
+        while running.load(Ordering::SeqCst) {
+            match pipe.accept() {
+                Ok(true) => {
+                    if let Err(e) = serve(&pipe, &sender, &running) {
+                        eprintln!("control pipe: {}", e);
+                    }
+                    pipe.disconnect();
+                }
+                Ok(false) => thread::sleep(Duration::from_millis(100)),
+                Err(e) => {
+                    eprintln!("control pipe: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+#[cfg(windows)]
+fn serve(pipe: &pipe::Pipe, sender: &Sender<Request>, running: &AtomicBool) -> io::Result<()> {
+    let mut pending = Vec::new();
+    let mut buf = [0; 512];
+    let mut quiet_since = Instant::now();
+    while running.load(Ordering::SeqCst) {
+        match pipe.read(&mut buf)? {
+            // the client closed its end
+            Some(0) => break,
+            Some(read) => {
+                quiet_since = Instant::now();
+                pending.extend_from_slice(&buf[..read]);
+                while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    if let Some(answer) = answer(&String::from_utf8_lossy(&line), sender) {
+                        pipe.write_all(format!("{}\n", answer).as_bytes())?;
+                    }
+                }
+            }
+            // a client that went quiet
+            None if quiet_since.elapsed() >= CLIENT_TIMEOUT => break,
+            None => thread::sleep(Duration::from_millis(10)),
+        }
+    }
+    // a last command without a line break, whose client may be gone already
+    if let Some(answer) = answer(&String::from_utf8_lossy(&pending), sender) {
+        let _ = pipe.write_all(format!("{}\n", answer).as_bytes());
+    }
+    Ok(())
+}
+
+/// Queues the command on `line`, giving what to answer, or nothing for an
+/// empty line.
+#[cfg(any(unix, windows))]
+fn answer(line: &str, sender: &Sender<Request>) -> Option<String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    Some(match line.parse::<Request>() {
+        Ok(request) => {
+            let _ = sender.send(request);
+            "ok".to_string()
+        }
+        Err(e) => format!("error: {}", e),
+    })
+}
+
+/// Just enough of the named pipe api, in non-blocking mode so that the
+/// worker notices the shutdown.
+#[cfg(windows)]
+mod pipe {
+    use std::{
+        ffi::c_void, io, os::windows::ffi::OsStrExt, path::Path, ptr, thread, time::Duration,
+    };
+
+    type Handle = isize;
+
+    const INVALID_HANDLE_VALUE: Handle = -1;
+    const PIPE_ACCESS_DUPLEX: u32 = 0x3;
+    const PIPE_TYPE_BYTE: u32 = 0x0;
+    const PIPE_NOWAIT: u32 = 0x1;
+    const PIPE_REJECT_REMOTE_CLIENTS: u32 = 0x8;
+    const BUFFER_SIZE: u32 = 4096;
+    const ERROR_BROKEN_PIPE: i32 = 109;
+    const ERROR_NO_DATA: i32 = 232;
+    const ERROR_PIPE_CONNECTED: i32 = 535;
+    const ERROR_PIPE_LISTENING: i32 = 536;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateNamedPipeW(
+            name: *const u16,
+            open_mode: u32,
+            pipe_mode: u32,
+            max_instances: u32,
+            out_buffer_size: u32,
+            in_buffer_size: u32,
+            default_time_out: u32,
+            security_attributes: *mut c_void,
+        ) -> Handle;
+        fn ConnectNamedPipe(pipe: Handle, overlapped: *mut c_void) -> i32;
+        fn DisconnectNamedPipe(pipe: Handle) -> i32;
+        fn ReadFile(
+            file: Handle,
+            buffer: *mut u8,
+            len: u32,
+            read: *mut u32,
+            overlapped: *mut c_void,
+        ) -> i32;
+        fn WriteFile(
+            file: Handle,
+            buffer: *const u8,
+            len: u32,
+            written: *mut u32,
+            overlapped: *mut c_void,
+        ) -> i32;
+        fn CloseHandle(handle: Handle) -> i32;
+    }
+
+    /// The one instance of the pipe, serving a client at a time.
+    pub struct Pipe(Handle);
+
+    impl Pipe {
+        pub fn create(path: &Path) -> io::Result<Self> {
+            let name: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+            // SAFETY: `name` is nul-terminated, no security attributes
+            let handle = unsafe {
+                CreateNamedPipeW(
+                    name.as_ptr(),
+                    PIPE_ACCESS_DUPLEX,
+                    PIPE_TYPE_BYTE | PIPE_NOWAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                    1,
+                    BUFFER_SIZE,
+                    BUFFER_SIZE,
+                    0,
+                    ptr::null_mut(),
+                )
+            };
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self(handle))
+        }
+
+        /// Whether a client is connected, without waiting for one.
+        pub fn accept(&self) -> io::Result<bool> {
+            // SAFETY: our pipe handle, without overlapped io; in non-blocking
+            // mode success only means it is free for a new client
+            if unsafe { ConnectNamedPipe(self.0, ptr::null_mut()) } != 0 {
+                return Ok(false);
+            }
+            let e = io::Error::last_os_error();
+            match e.raw_os_error() {
+                Some(ERROR_PIPE_CONNECTED) => Ok(true),
+                Some(ERROR_PIPE_LISTENING) => Ok(false),
+                // a client that left before it was served
+                Some(ERROR_NO_DATA) => {
+                    self.disconnect();
+                    Ok(false)
+                }
+                _ => Err(e),
+            }
+        }
+
+        /// What the client wrote so far: `None` when there is nothing yet,
+        /// `Some(0)` once it closed its end.
+        pub fn read(&self, buf: &mut [u8]) -> io::Result<Option<usize>> {
+            let mut read = 0;
+            // SAFETY: `buf` is valid for its length, without overlapped io
+            let ok = unsafe {
+                ReadFile(
+                    self.0,
+                    buf.as_mut_ptr(),
+                    buf.len() as u32,
+                    &mut read,
+                    ptr::null_mut(),
+                )
+            };
+            if ok != 0 {
+                return Ok((read > 0).then_some(read as usize));
+            }
+            let e = io::Error::last_os_error();
+            match e.raw_os_error() {
+                Some(ERROR_NO_DATA) => Ok(None),
+                Some(ERROR_BROKEN_PIPE) => Ok(Some(0)),
+                _ => Err(e),
+            }
+        }
+
+        pub fn write_all(&self, mut data: &[u8]) -> io::Result<()> {
+            while !data.is_empty() {
+                let mut written = 0;
+                // SAFETY: `data` is valid for its length, without overlapped io
+                let ok = unsafe {
+                    WriteFile(
+                        self.0,
+                        data.as_ptr(),
+                        data.len() as u32,
+                        &mut written,
+                        ptr::null_mut(),
+                    )
+                };
+                if ok == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if written == 0 {
+                    // the client hasn't read the earlier answers yet
+                    thread::sleep(Duration::from_millis(10));
+                }
+                data = &data[written as usize..];
+            }
+            Ok(())
+        }
+
+        /// Drops the client, so the next one can connect.
+        pub fn disconnect(&self) {
+            // SAFETY: our pipe handle
+            unsafe { DisconnectNamedPipe(self.0) };
+        }
+    }
+
+    impl Drop for Pipe {
+        fn drop(&mut self) {
+            // SAFETY: our pipe handle, not used after this
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    fn next(running: &AtomicBool, requests: &mut Requests) -> Option<Request> {
+        match wait(running, Duration::from_millis(50), Some(requests)) {
+            Wake::Request(request) => Some(request),
+            Wake::Tick | Wake::Stop => None,
+        }
+    }
+
+    #[test]
+    fn sends_coalesce_but_toggles_keep_their_order() {
+        let running = AtomicBool::new(true);
+        let (sender, receiver) = mpsc::channel();
+        for request in [
+            Request::Send,
+            Request::Say("first".to_string()),
+            Request::Toggle(ProviderKind::Cpu, false),
+            Request::Toggle(ProviderKind::Uptime, true),
+            Request::Send,
+            Request::Say("last".to_string()),
+        ] {
+            sender.send(request).unwrap();
+        }
+        let mut requests = Requests::new(receiver);
+        assert_eq!(
+            next(&running, &mut requests),
+            Some(Request::Say("first".to_string()))
+        );
+        assert_eq!(
+            next(&running, &mut requests),
+            Some(Request::Toggle(ProviderKind::Cpu, false))
+        );
+        assert_eq!(
+            next(&running, &mut requests),
+            Some(Request::Toggle(ProviderKind::Uptime, true))
+        );
+        assert_eq!(
+            next(&running, &mut requests),
+            Some(Request::Say("last".to_string()))
+        );
+        assert_eq!(next(&running, &mut requests), None);
+    }
+
+    #[test]
+    fn a_stopped_listener_still_waits_for_the_deadline() {
+        let running = AtomicBool::new(true);
+        let (sender, receiver) = mpsc::channel::<Request>();
+        drop(sender);
+        let mut requests = Requests::new(receiver);
+        let started = Instant::now();
+        assert!(matches!(
+            wait(&running, Duration::from_millis(250), Some(&mut requests)),
+            Wake::Tick
+        ));
+        assert!(started.elapsed() >= Duration::from_millis(250));
+    }
+
+    #[test]
+    fn commands_parse() {
+        assert_eq!("send".parse(), Ok(Request::Send));
+        assert_eq!(
+            "say hi there".parse(),
+            Ok(Request::Say("hi there".to_string()))
+        );
+        assert_eq!(
+            "disable cpu".parse(),
+            Ok(Request::Toggle(ProviderKind::Cpu, false))
+        );
+        assert_eq!(
+            "enable self".parse(),
+            Ok(Request::Toggle(ProviderKind::SelfUsage, true))
+        );
+        assert!("say".parse::<Request>().is_err());
+        assert!("enable nothing".parse::<Request>().is_err());
+        assert!("send now".parse::<Request>().is_err());
+    }
+}
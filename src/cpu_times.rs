@@ -0,0 +1,55 @@
+/// Splits cpu usage into time spent in programs and in the kernel, from the
+/// difference between two reads of the cpu time counters. Only Linux exposes
+/// them, elsewhere there is never a breakdown.
+#[derive(Default)]
+pub struct CpuTimes {
+    last: Option<Counters>,
+}
+
+#[derive(Clone, Copy)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct Counters {
+    user: u64,
+    system: u64,
+    total: u64,
+}
+
+impl CpuTimes {
+    /// User and system usage in percent since the last call.
+    pub fn read(&mut self) -> Option<(f32, f32)> {
+        let now = counters()?;
+        let last = self.last.replace(now)?;
+        let total = now.total.checked_sub(last.total).filter(|t| *t > 0)? as f32;
+        Some((
+            now.user.saturating_sub(last.user) as f32 / total * 100.0,
+            now.system.saturating_sub(last.system) as f32 / total * 100.0,
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn counters() -> Option<Counters> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    // cpu user nice system idle iowait irq softirq steal ...
+    let fields: Vec<u64> = stat
+        .lines()
+        .next()?
+        .strip_prefix("cpu ")?
+        .split_whitespace()
+        .take(8)
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    if fields.len() < 8 {
+        return None;
+    }
+    Some(Counters {
+        user: fields[0] + fields[1],
+        system: fields[2] + fields[5] + fields[6],
+        total: fields.iter().sum(),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn counters() -> Option<Counters> {
+    None
+}
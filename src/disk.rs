@@ -0,0 +1,137 @@
+use std::{collections::HashSet, path::PathBuf, rc::Rc, str::FromStr};
+
+use sysinfo::{Disk, Disks};
+
+use crate::{
+    labels::{Label, Labels},
+    Info, InfoResult,
+};
+
+/// Which disks to show, given as a mount point, `all` or `total`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiskSelection {
+    /// One line per mounted disk
+    All,
+    /// One line adding up every disk
+    Total,
+    /// The disk mounted here
+    Mount(PathBuf),
+}
+
+impl FromStr for DiskSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "total" => Ok(Self::Total),
+            "" => Err("expected a mount point, `all` or `total`".to_string()),
+            mount => Ok(Self::Mount(PathBuf::from(mount))),
+        }
+    }
+}
+
+pub struct DiskInfo {
+    disks: Disks,
+    selections: Vec<DiskSelection>,
+    labels: Rc<Labels>,
+}
+
+impl DiskInfo {
+    pub fn new(selections: Vec<DiskSelection>, labels: Rc<Labels>) -> Self {
+        Self {
+            disks: Disks::new_with_refreshed_list(),
+            selections,
+            labels,
+        }
+    }
+
+    /// The shown disks as `(name, used, total)`, `None` for a mount point
+    /// that isn't mounted right now.
+    fn usage(&mut self) -> Vec<(String, Option<(u64, u64)>)> {
+        // removable disks come and go, so look for them again every time
+        self.disks.refresh_list();
+        let mut disks: Vec<&Disk> = self
+            .disks
+            .iter()
+            .filter(|disk| disk.total_space() > 0)
+            .collect();
+        disks.sort_by(|a, b| a.mount_point().cmp(b.mount_point()));
+        let used = |disk: &Disk| disk.total_space().saturating_sub(disk.available_space());
+
+        let mut usage = Vec::new();
+        for selection in &self.selections {
+            match selection {
+                DiskSelection::All => usage.extend(disks.iter().map(|disk| {
+                    (
+                        disk.mount_point().display().to_string(),
+                        Some((used(disk), disk.total_space())),
+                    )
+                })),
+                DiskSelection::Total => {
+                    // a disk mounted in several places counts once
+                    let mut seen = HashSet::new();
+                    let (used, total) = disks
+                        .iter()
+                        .filter(|disk| seen.insert(disk.name()))
+                        .fold((0, 0), |(u, t), disk| {
+                            (u + used(disk), t + disk.total_space())
+                        });
+                    usage.push(("total".to_string(), Some((used, total))));
+                }
+                DiskSelection::Mount(mount) => usage.push((
+                    mount.display().to_string(),
+                    disks
+                        .iter()
+                        .find(|disk| disk.mount_point() == mount)
+                        .map(|disk| (used(disk), disk.total_space())),
+                )),
+            }
+        }
+        usage
+    }
+}
+
+impl Info for DiskInfo {
+    fn name(&self) -> &str {
+        "disk"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        let label = self.labels.get(Label::Disk).to_string();
+        let lines: Vec<_> = self
+            .usage()
+            .into_iter()
+            .map(|(name, usage)| match usage {
+                Some((used, total)) => format!(
+                    "{} {}: {}/{} ({})",
+                    label,
+                    name,
+                    self.labels.size(used),
+                    self.labels.size(total),
+                    self.labels.percent(used as f32 / total as f32 * 100.0, 2),
+                ),
+                None => format!("{} {}: --", label, name),
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    fn get_info_compact(&mut self) -> InfoResult {
+        let label = self.labels.get(Label::Disk).to_string();
+        let parts: Vec<_> = self
+            .usage()
+            .into_iter()
+            .map(|(name, usage)| match usage {
+                Some((used, total)) => format!(
+                    "{}{} {}",
+                    label,
+                    name,
+                    self.labels.percent(used as f32 / total as f32 * 100.0, 0)
+                ),
+                None => format!("{}{} --", label, name),
+            })
+            .collect();
+        Ok(parts.join(" "))
+    }
+}
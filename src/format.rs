@@ -0,0 +1,223 @@
+/// Replaces symbols that chatbox fonts often lack with plain ascii, e.g.
+/// `49°C` becomes `49C`, `→` becomes `->` and emoji become `?`. Letters are
+/// kept, except that German umlauts are spelled out, e.g. `lädt` as `laedt`.
+pub fn to_ascii(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            c if c.is_ascii() => out.push(c),
+            '°' => {}
+            '→' => out.push_str("->"),
+            '←' => out.push_str("<-"),
+            '↑' => out.push('^'),
+            '↓' => out.push('v'),
+            '…' => out.push_str("..."),
+            '█' => out.push('#'),
+            '░' => out.push('-'),
+            '×' => out.push('x'),
+            'µ' => out.push('u'),
+            '–' | '—' => out.push('-'),
+            '‘' | '’' => out.push('\''),
+            '“' | '”' => out.push('"'),
+            'ä' => out.push_str("ae"),
+            'ö' => out.push_str("oe"),
+            'ü' => out.push_str("ue"),
+            'Ä' => out.push_str("Ae"),
+            'Ö' => out.push_str("Oe"),
+            'Ü' => out.push_str("Ue"),
+            'ß' => out.push_str("ss"),
+            // the invisible glue of emoji sequences
+            '\u{200D}' | '\u{FE0F}' => {}
+            c if is_emoji(c) => out.push('?'),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Whether `c` is in one of the emoji, pictograph or dingbat blocks.
+fn is_emoji(c: char) -> bool {
+    matches!(c, '\u{2600}'..='\u{27BF}' | '\u{1F000}'..='\u{1FAFF}')
+}
+
+/// `value` with `decimals` decimals; `trim` drops the trailing zeros and
+/// the point if nothing is left after it, e.g. `5.00` becomes `5` and
+/// `38.40` becomes `38.4`.
+pub fn number(value: f64, decimals: usize, trim: bool) -> String {
+    let number = format!("{:.*}", decimals, value);
+    if trim {
+        trim_zeros(&number)
+    } else {
+        number
+    }
+}
+
+/// Drops the trailing zeros of a decimal number, e.g. `38.40` becomes `38.4`.
+pub fn trim_zeros(number: &str) -> String {
+    if !number.contains('.') {
+        return number.to_string();
+    }
+    number
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Makes a process name safe to show: control characters become `?` and a
+/// name longer than `max_chars` is cut short with `…`. Names that aren't
+/// UTF-8 are already converted lossily by sysinfo.
+pub fn process_name(name: &str, max_chars: usize) -> String {
+    let clean = name.chars().map(|c| if c.is_control() { '?' } else { c });
+    if name.chars().count() <= max_chars {
+        return clean.collect();
+    }
+    clean
+        .take(max_chars.saturating_sub(1))
+        .chain(std::iter::once('…'))
+        .collect()
+}
+
+/// A bar filled to `percent`, e.g. `[████░░░░░░]` for 38% and a `width` of
+/// 10.
+pub fn bar(percent: f32, width: u64) -> String {
+    let width = width as usize;
+    let filled = ((percent / 100.0 * width as f32).round() as usize).min(width);
+    format!("[{}{}]", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// How durations like uptime are written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DurationFormat {
+    /// The largest units down to minutes, e.g. `2d 3h 42m`
+    Human,
+    /// Hours, minutes and seconds, e.g. `51:42:10`
+    Clock,
+    /// A pattern using `%D` days, `%H` hours of the day, `%h` total hours,
+    /// `%M` minutes of the hour, `%m` total minutes, `%S` seconds of the
+    /// minute, `%s` total seconds and `%%`
+    Pattern(String),
+}
+
+impl std::str::FromStr for DurationFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "clock" => Ok(Self::Clock),
+            pattern if pattern.contains('%') => Ok(Self::Pattern(pattern.to_string())),
+            other => Err(format!(
+                "expected `human`, `clock` or a pattern like `%h:%M`, got `{}`",
+                other
+            )),
+        }
+    }
+}
+
+pub fn format_duration(secs: u64, format: &DurationFormat) -> String {
+    let (days, hours, minutes, seconds) =
+        (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    match format {
+        DurationFormat::Human => {
+            if secs < 60 {
+                return format!("{}s", seconds);
+            }
+            let mut parts = Vec::new();
+            if days > 0 {
+                parts.push(format!("{}d", days));
+            }
+            if days > 0 || hours > 0 {
+                parts.push(format!("{}h", hours));
+            }
+            parts.push(format!("{}m", minutes));
+            parts.join(" ")
+        }
+        DurationFormat::Clock => format!("{}:{:02}:{:02}", secs / 3600, minutes, seconds),
+        DurationFormat::Pattern(pattern) => {
+            let mut out = String::new();
+            let mut chars = pattern.chars();
+            while let Some(c) = chars.next() {
+                if c != '%' {
+                    out.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('D') => out.push_str(&days.to_string()),
+                    Some('H') => out.push_str(&format!("{:02}", hours)),
+                    Some('h') => out.push_str(&(secs / 3600).to_string()),
+                    Some('M') => out.push_str(&format!("{:02}", minutes)),
+                    Some('m') => out.push_str(&(secs / 60).to_string()),
+                    Some('S') => out.push_str(&format!("{:02}", seconds)),
+                    Some('s') => out.push_str(&secs.to_string()),
+                    Some('%') => out.push('%'),
+                    Some(other) => {
+                        out.push('%');
+                        out.push(other);
+                    }
+                    None => out.push('%'),
+                }
+            }
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_ascii_replaces_symbols() {
+        assert_eq!(to_ascii("49°C ↑ [██░]"), "49C ^ [##-]");
+        assert_eq!(to_ascii("GPU 🔥 ok ❤️"), "GPU ? ok ?");
+        assert_eq!(to_ascii("👍🏽"), "??");
+    }
+
+    #[test]
+    fn to_ascii_keeps_letters() {
+        assert_eq!(to_ascii("Akku: 85% (lädt)"), "Akku: 85% (laedt)");
+        assert_eq!(to_ascii("Größe Übung"), "Groesse Uebung");
+        assert_eq!(to_ascii("メモリ: 離席 café"), "メモリ: 離席 café");
+    }
+
+    #[test]
+    fn number_trims_only_when_asked() {
+        assert_eq!(number(5.0, 2, false), "5.00");
+        assert_eq!(number(5.0, 2, true), "5");
+        assert_eq!(number(38.4, 2, true), "38.4");
+        assert_eq!(number(100.0, 0, true), "100");
+        assert_eq!(number(-0.5, 2, true), "-0.5");
+    }
+
+    #[test]
+    fn process_name_replaces_control_characters() {
+        assert_eq!(process_name("bad\nname\t\u{1b}[31m", 24), "bad?name??[31m");
+    }
+
+    #[test]
+    fn process_name_keeps_names_up_to_the_limit() {
+        assert_eq!(process_name("VRChat.exe", 10), "VRChat.exe");
+        assert_eq!(process_name("", 10), "");
+    }
+
+    #[test]
+    fn process_name_cuts_long_names_to_the_limit() {
+        let long = "a".repeat(300);
+        let shown = process_name(&long, 24);
+        assert_eq!(shown.chars().count(), 24);
+        assert_eq!(shown, format!("{}…", "a".repeat(23)));
+    }
+
+    #[test]
+    fn process_name_cuts_between_characters() {
+        // 3 bytes each, so a byte count would cut one in half
+        assert_eq!(process_name("日本語のプロセス", 4), "日本語…");
+        assert_eq!(process_name("日本語", 3), "日本語");
+    }
+
+    #[test]
+    fn process_name_of_one_character_is_just_the_ellipsis() {
+        assert_eq!(process_name("VRChat", 1), "…");
+        assert_eq!(process_name("V", 1), "V");
+    }
+}
@@ -0,0 +1,422 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    str::FromStr,
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    workers::{self, Workers},
+    Info, InfoResult,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A value fetched from an http endpoint, given as
+/// `url=http://...,path=$.main.temp,label=Weather[,interval=60]`.
+#[derive(Clone, Debug)]
+pub struct HttpSource {
+    url: Url,
+    path: Vec<PathSegment>,
+    label: String,
+    interval: Duration,
+}
+
+impl FromStr for HttpSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut url = None;
+        let mut path = None;
+        let mut label = None;
+        let mut interval = Duration::from_secs(60);
+        for pair in s.split(',') {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected `key=value`, got `{}`", pair))?;
+            match key.trim() {
+                "url" => url = Some(value.trim().parse::<Url>()?),
+                "path" => path = Some(parse_path(value.trim())?),
+                "label" => label = Some(value.trim().to_string()),
+                "interval" => {
+                    let secs: u64 = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("invalid interval `{}`", value))?;
+                    if secs == 0 {
+                        return Err("interval must be at least 1 second".to_string());
+                    }
+                    interval = Duration::from_secs(secs);
+                }
+                other => return Err(format!("unknown key `{}`", other)),
+            }
+        }
+        Ok(Self {
+            url: url.ok_or("missing `url`")?,
+            path: path.unwrap_or_default(),
+            label: label.ok_or("missing `label`")?,
+            interval,
+        })
+    }
+}
+
+pub struct HttpInfo {
+    label: String,
+    value: Arc<Mutex<Option<String>>>,
+}
+
+impl HttpInfo {
+    /// Starts polling `source` on a worker thread.
+    pub fn new(source: HttpSource, workers: &mut Workers) -> Self {
+        let value = Arc::new(Mutex::new(None));
+        let label = source.label.clone();
+        let cache = Arc::clone(&value);
+        let running = workers.running();
+        workers.spawn(&format!("http source {}", label), move || {
+            while running.load(Ordering::SeqCst) {
+                match fetch(&source) {
+                    Ok(v) => *cache.lock().unwrap() = Some(v),
+                    Err(e) => eprintln!("{}: {}", source.label, e),
+                }
+                workers::sleep_while(&running, source.interval);
+            }
+        });
+        Self { label, value }
+    }
+}
+
+impl Info for HttpInfo {
+    fn name(&self) -> &str {
+        &self.label
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        Ok(match self.value.lock().unwrap().as_deref() {
+            Some(value) => format!("{}: {}", self.label, value),
+            None => format!("{}: --", self.label),
+        })
+    }
+}
+
+fn fetch(source: &HttpSource) -> io::Result<String> {
+    let body = get(&source.url)?;
+    let json = Json::parse(&body).map_err(invalid_data)?;
+    let value = json
+        .select(&source.path)
+        .ok_or_else(|| invalid_data("path not found in response"))?;
+    Ok(value.to_string())
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[derive(Clone, Debug)]
+struct Url {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl FromStr for Url {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("only http:// urls are supported, got `{}`", s))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("invalid port in `{}`", s))?,
+            ),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(format!("missing host in `{}`", s));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// A minimal HTTP/1.1 GET, enough for small JSON APIs.
+fn get(url: &Url) -> io::Result<String> {
+    let addr = (url.host.as_str(), url.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host not found"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\n\r\n",
+        url.path, url.host
+    )?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| invalid_data("malformed response"))?;
+
+    let mut lines = head.lines();
+    let status = lines.next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(io::Error::other(format!("unexpected status `{}`", status)));
+    }
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding")
+                && value.trim().eq_ignore_ascii_case("chunked")
+        })
+    });
+    if chunked {
+        dechunk(body)
+    } else {
+        Ok(body.to_string())
+    }
+}
+
+fn dechunk(mut body: &str) -> io::Result<String> {
+    let mut out = String::new();
+    loop {
+        let (size, rest) = body
+            .split_once("\r\n")
+            .ok_or_else(|| invalid_data("malformed chunk"))?;
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size =
+            usize::from_str_radix(size, 16).map_err(|_| invalid_data("malformed chunk size"))?;
+        if size == 0 {
+            return Ok(out);
+        }
+        let chunk = rest
+            .get(..size)
+            .ok_or_else(|| invalid_data("truncated chunk"))?;
+        out.push_str(chunk);
+        body = rest[size..].trim_start_matches("\r\n");
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Parses a path like `$.main.temp` or `$.list[0].name`.
+fn parse_path(s: &str) -> Result<Vec<PathSegment>, String> {
+    let invalid = || format!("invalid path `{}`", s);
+    let rest = s.strip_prefix('$').unwrap_or(s);
+    let mut segments = Vec::new();
+    for part in rest.split('.').filter(|p| !p.is_empty()) {
+        let (key, mut indices) = match part.find('[') {
+            Some(i) => (&part[..i], &part[i..]),
+            None => (part, ""),
+        };
+        if !key.is_empty() {
+            segments.push(PathSegment::Key(key.to_string()));
+        }
+        while let Some(after) = indices.strip_prefix('[') {
+            let (index, rest) = after.split_once(']').ok_or_else(invalid)?;
+            segments.push(PathSegment::Index(index.parse().map_err(|_| invalid())?));
+            indices = rest;
+        }
+        if !indices.is_empty() {
+            return Err(invalid());
+        }
+    }
+    Ok(segments)
+}
+
+#[derive(Debug)]
+enum Json {
+    Null,
+    Bool(bool),
+    /// Kept as written so it is shown the way the endpoint sent it.
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.pos != parser.chars.len() {
+            return Err("trailing characters after json".to_string());
+        }
+        Ok(value)
+    }
+
+    fn select(&self, path: &[PathSegment]) -> Option<&Json> {
+        path.iter()
+            .try_fold(self, |value, segment| match (value, segment) {
+                (Json::Object(map), PathSegment::Key(key)) => map.get(key),
+                (Json::Array(items), PathSegment::Index(i)) => items.get(*i),
+                _ => None,
+            })
+    }
+}
+
+impl std::fmt::Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write!(f, "{}", s),
+            Json::Array(items) => write!(f, "[{} items]", items.len()),
+            Json::Object(map) => write!(f, "{{{} keys}}", map.len()),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(format!("expected `{}` at {}", expected, self.pos - 1)),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for c in word.chars() {
+            self.expect(c)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('n') => self.literal("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => Ok(self.number()),
+            _ => Err(format!("unexpected input at {}", self.pos)),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut map = BTreeMap::new();
+        self.whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Json::Object(map));
+        }
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(':')?;
+            map.insert(key, self.value()?);
+            self.whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(map)),
+                _ => return Err(format!("expected `,` or `}}` at {}", self.pos - 1)),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(items)),
+                _ => return Err(format!("expected `,` or `]` at {}", self.pos - 1)),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(out),
+                Some('\\') => match self.next() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.next()).collect();
+                        let code = u32::from_str_radix(&hex, 16)
+                            .map_err(|_| format!("invalid escape at {}", self.pos))?;
+                        out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    Some(c) => out.push(c),
+                    None => break,
+                },
+                Some(c) => out.push(c),
+                None => break,
+            }
+        }
+        Err("unterminated string".to_string())
+    }
+
+    fn number(&mut self) -> Json {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            self.pos += 1;
+        }
+        Json::Number(self.chars[start..self.pos].iter().collect())
+    }
+}
@@ -0,0 +1,77 @@
+use std::{rc::Rc, time::Duration};
+
+use crate::{
+    format::{self, DurationFormat},
+    labels::{Label, Labels},
+    Info, InfoResult,
+};
+
+/// Whether this system can tell how long ago the last input was.
+pub const SUPPORTED: bool = cfg!(windows);
+
+/// Shows `active`, or `idle 5m` once there was no keyboard or mouse input for
+/// `after`.
+pub struct UserIdleInfo {
+    after: Duration,
+    labels: Rc<Labels>,
+}
+
+impl UserIdleInfo {
+    pub fn new(after: Duration, labels: Rc<Labels>) -> Self {
+        Self { after, labels }
+    }
+}
+
+impl Info for UserIdleInfo {
+    fn name(&self) -> &str {
+        "idle"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        Ok(match idle_time() {
+            Some(idle) if idle >= self.after => format!(
+                "{} {}",
+                self.labels.get(Label::Idle),
+                format::format_duration(idle.as_secs(), &DurationFormat::Human)
+            ),
+            Some(_) => self.labels.get(Label::Active).to_string(),
+            None => format!("{} --", self.labels.get(Label::Idle)),
+        })
+    }
+}
+
+#[cfg(windows)]
+fn idle_time() -> Option<Duration> {
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetTickCount() -> u32;
+    }
+
+    let mut info = LastInputInfo {
+        cb_size: std::mem::size_of::<LastInputInfo>() as u32,
+        dw_time: 0,
+    };
+    // SAFETY: `info` is a LASTINPUTINFO with its size filled in
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    // SAFETY: no arguments; both tick counts wrap around after 49.7 days
+    let now = unsafe { GetTickCount() };
+    Some(Duration::from_millis(now.wrapping_sub(info.dw_time) as u64))
+}
+
+#[cfg(not(windows))]
+fn idle_time() -> Option<Duration> {
+    None
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+
+use crate::{
+    cli::{Lang, TempUnit},
+    format,
+};
+
+/// A word that introduces a value in a provider's line, e.g. `CPU` in
+/// `CPU: 5.00%`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Label {
+    Cpu,
+    Processes,
+    Ram,
+    Gpu,
+    Video,
+    Pstate,
+    MemUtil,
+    Vram,
+    Disk,
+    Uptime,
+    Fps,
+    SelfUsage,
+    Active,
+    Idle,
+    Battery,
+    Charging,
+}
+
+impl Label {
+    /// The built-in text for `lang`.
+    pub fn text(self, lang: Lang) -> &'static str {
+        match (lang, self) {
+            (Lang::Ja, Self::Processes) => "プロセス",
+            (Lang::Ja, Self::Ram) => "メモリ",
+            (Lang::Ja, Self::Video) => "ビデオ",
+            (Lang::Ja, Self::Uptime) => "稼働時間",
+            (Lang::Ja, Self::Disk) => "ディスク",
+            (Lang::De, Self::Processes) => "Prozesse",
+            (Lang::De, Self::Uptime) => "Laufzeit",
+            (Lang::Ja, Self::Active) => "在席",
+            (Lang::Ja, Self::Idle) => "離席",
+            (Lang::Ja, Self::Battery) => "バッテリー",
+            (Lang::Ja, Self::Charging) => "充電中",
+            (Lang::De, Self::Disk) => "Laufwerk",
+            (Lang::De, Self::Active) => "aktiv",
+            (Lang::De, Self::Idle) => "inaktiv",
+            (Lang::De, Self::Battery) => "Akku",
+            (Lang::De, Self::Charging) => "lädt",
+            (_, Self::Cpu) => "CPU",
+            (_, Self::Processes) => "Processes",
+            (_, Self::Ram) => "RAM",
+            (_, Self::Gpu) => "GPU",
+            (_, Self::Video) => "Video",
+            (_, Self::Pstate) => "Pstate",
+            (_, Self::MemUtil) => "MemUtil",
+            (_, Self::Vram) => "VRAM",
+            (_, Self::Disk) => "Disk",
+            (_, Self::Uptime) => "Uptime",
+            (_, Self::Fps) => "FPS",
+            (_, Self::SelfUsage) => "self",
+            (_, Self::Active) => "active",
+            (_, Self::Idle) => "idle",
+            (_, Self::Battery) => "Battery",
+            (_, Self::Charging) => "charging",
+        }
+    }
+}
+
+/// The date and time format conventional for `lang`.
+pub fn time_format(lang: Lang) -> &'static str {
+    match lang {
+        Lang::En => "%m/%d/%Y %H:%M:%S UTC%:::z",
+        Lang::Ja => "%Y/%m/%d %H:%M:%S UTC%:::z",
+        Lang::De => "%d.%m.%Y %H:%M:%S UTC%:::z",
+    }
+}
+
+/// A replacement text for a label, parsed from `label=text`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabelText {
+    pub label: Label,
+    pub text: String,
+}
+
+impl std::str::FromStr for LabelText {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((label, text)) = s.split_once('=') else {
+            return Err(format!(
+                "expected `label=text`, e.g. `cpu=Proc`, got `{}`",
+                s
+            ));
+        };
+        Ok(Self {
+            label: Label::from_str(label.trim(), true).map_err(|_| {
+                let names: Vec<_> = Label::value_variants()
+                    .iter()
+                    .filter_map(|label| label.to_possible_value())
+                    .map(|value| value.get_name().to_string())
+                    .collect();
+                format!(
+                    "unknown label `{}`, expected one of {}",
+                    label,
+                    names.join(", ")
+                )
+            })?,
+            text: text.to_string(),
+        })
+    }
+}
+
+/// The labels every provider uses, in one language with `--label`
+/// replacements applied, and how their numbers are written.
+#[derive(Debug)]
+pub struct Labels {
+    lang: Lang,
+    texts: HashMap<Label, String>,
+    trim_zeros: bool,
+    units: bool,
+}
+
+impl Labels {
+    pub fn new(lang: Lang, texts: &[LabelText]) -> Self {
+        Self {
+            lang,
+            texts: texts.iter().map(|t| (t.label, t.text.clone())).collect(),
+            trim_zeros: false,
+            units: true,
+        }
+    }
+
+    /// Writes numbers without their trailing zeros (`--trim-zeros`) or
+    /// without their units (`--no-units`).
+    pub fn with_numbers(mut self, trim_zeros: bool, no_units: bool) -> Self {
+        self.trim_zeros = trim_zeros;
+        self.units = !no_units;
+        self
+    }
+
+    pub fn get(&self, label: Label) -> &str {
+        self.texts
+            .get(&label)
+            .map(String::as_str)
+            .unwrap_or(label.text(self.lang))
+    }
+
+    /// `value` with `decimals` decimals, e.g. `5.00`, or `5` with
+    /// `--trim-zeros`.
+    pub fn number(&self, value: impl Into<f64>, decimals: usize) -> String {
+        format::number(value.into(), decimals, self.trim_zeros)
+    }
+
+    /// `unit`, unless `--no-units` drops it.
+    pub fn unit<'a>(&self, unit: &'a str) -> &'a str {
+        if self.units {
+            unit
+        } else {
+            ""
+        }
+    }
+
+    /// e.g. `38.40%`
+    pub fn percent(&self, value: impl Into<f64>, decimals: usize) -> String {
+        format!("{}{}", self.number(value, decimals), self.unit("%"))
+    }
+
+    /// A sensor reading in °C shown in `unit`, e.g. `65°C`, or `65C` when
+    /// compact.
+    pub fn temperature(&self, celsius: f32, unit: TempUnit, compact: bool) -> String {
+        let value = self.number(unit.convert(celsius), 0);
+        match (self.units, compact) {
+            (false, _) => value,
+            (true, true) => format!("{}{}", value, unit.symbol()),
+            (true, false) => format!("{}°{}", value, unit.symbol()),
+        }
+    }
+
+    /// A bar filled to `percent` and its rounded percentage, e.g.
+    /// `[████░░░░░░] 38%` for a `width` of 10.
+    pub fn bar(&self, percent: f32, width: u64) -> String {
+        format!(
+            "{} {}",
+            format::bar(percent, width),
+            self.percent(percent, 0)
+        )
+    }
+
+    /// A size like `12.3 GiB`, or `12 GiB` with `--trim-zeros`. Sizes keep
+    /// their unit with `--no-units`, as `1.2 GiB` and `1.2 MiB` would be too
+    /// easy to mix up.
+    pub fn size(&self, bytes: u64) -> String {
+        let size = bytesize::to_string(bytes, true);
+        match size.split_once(' ') {
+            Some((value, unit)) if self.trim_zeros => {
+                format!("{} {}", format::trim_zeros(value), unit)
+            }
+            _ => size,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::UdpSocket,
+    rc::Rc,
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::Duration,
+};
+
+use rosc::{OscPacket, OscType};
+
+use crate::{
+    labels::{Label, Labels},
+    params::Metric,
+    workers::Workers,
+    Info, InfoResult,
+};
+
+/// Latest numeric values received from the OSC server, by address.
+pub type Values = Arc<Mutex<HashMap<String, f32>>>;
+
+/// Listens for messages sent by the OSC server (VRChat sends to port 9001)
+/// and remembers the latest value of each of `addresses`.
+pub fn listen(
+    socket: UdpSocket,
+    addresses: Vec<String>,
+    workers: &mut Workers,
+) -> io::Result<Values> {
+    // wake up now and then to notice the shutdown
+    socket.set_read_timeout(Some(Duration::from_millis(500)))?;
+    let values = Values::default();
+    let received = Arc::clone(&values);
+    let running = workers.running();
+    workers.spawn("osc listener", move || {
+        let mut buf = [0; rosc::decoder::MTU];
+        while running.load(Ordering::SeqCst) {
+            let len = match socket.recv(&mut buf) {
+                Ok(len) => len,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock
+                            | io::ErrorKind::TimedOut
+                            | io::ErrorKind::ConnectionRefused
+                    ) =>
+                {
+                    continue
+                }
+                Err(e) => {
+                    eprintln!("osc listener: {}", e);
+                    break;
+                }
+            };
+            if let Ok((_, packet)) = rosc::decoder::decode_udp(&buf[..len]) {
+                store(&packet, &addresses, &received);
+            }
+        }
+    });
+    Ok(values)
+}
+
+fn store(packet: &OscPacket, addresses: &[String], values: &Values) {
+    match packet {
+        OscPacket::Message(msg) => {
+            if !addresses.contains(&msg.addr) {
+                return;
+            }
+            let value = match msg.args.first() {
+                Some(OscType::Float(v)) => *v,
+                Some(OscType::Double(v)) => *v as f32,
+                Some(OscType::Int(v)) => *v as f32,
+                Some(OscType::Long(v)) => *v as f32,
+                _ => return,
+            };
+            values.lock().unwrap().insert(msg.addr.clone(), value);
+        }
+        OscPacket::Bundle(bundle) => {
+            for packet in &bundle.content {
+                store(packet, addresses, values);
+            }
+        }
+    }
+}
+
+pub struct FpsInfo {
+    address: String,
+    values: Values,
+    labels: Rc<Labels>,
+}
+
+impl FpsInfo {
+    pub fn new(address: String, values: Values, labels: Rc<Labels>) -> Self {
+        Self {
+            address,
+            values,
+            labels,
+        }
+    }
+}
+
+impl Info for FpsInfo {
+    fn name(&self) -> &str {
+        "fps"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        let label = self.labels.get(Label::Fps);
+        Ok(match self.values.lock().unwrap().get(&self.address) {
+            Some(fps) => format!("{}: {:.0}", label, fps),
+            None => format!("{}: --", label),
+        })
+    }
+
+    fn get_info_compact(&mut self) -> InfoResult {
+        let label = self.labels.get(Label::Fps);
+        Ok(match self.values.lock().unwrap().get(&self.address) {
+            Some(fps) => format!("{}{:.0}", label, fps),
+            None => format!("{}--", label),
+        })
+    }
+
+    fn metric(&self, metric: Metric) -> Option<f32> {
+        match metric {
+            Metric::Fps => self.values.lock().unwrap().get(&self.address).copied(),
+            _ => None,
+        }
+    }
+}
@@ -1,137 +1,1203 @@
 use std::{
-    cell::RefCell,
-    io,
-    net::UdpSocket,
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    fmt, io,
     rc::Rc,
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Once,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
     vec,
 };
 
-use clap::Parser;
-use nvml_wrapper::{enum_wrappers::device::TemperatureSensor, Device, Nvml};
+mod battery;
+mod cli;
+mod config;
+mod control;
+mod cpu_times;
+mod disk;
+mod format;
+mod http;
+mod idle;
+mod labels;
+mod listener;
+mod params;
+mod plugins;
+mod record;
+mod sensor;
+mod service;
+mod transport;
+mod workers;
+
+use battery::BatteryInfo;
+use clap::{Parser, ValueEnum};
+use cli::{
+    Args, ChatboxArg, ChatboxMode, Command, OscTransport, Output, ProviderKind, RamFormat, RamMode,
+    TempUnit,
+};
+use control::{Request, Wake};
+use cpu_times::CpuTimes;
+use disk::DiskInfo;
+use http::HttpInfo;
+use idle::UserIdleInfo;
+use labels::{Label, Labels};
+use listener::FpsInfo;
+use nvml_wrapper::{
+    enum_wrappers::device::{Clock, PerformanceState, TemperatureSensor},
+    error::NvmlError,
+    struct_wrappers::device::{MemoryInfo, Utilization},
+    Device, Nvml,
+};
 use once_cell::sync::Lazy;
-use rosc::{OscMessage, OscPacket, OscType};
-use sysinfo::System;
+use params::{Level, Metric, Param};
+use plugins::PluginInfo;
+use record::Recorder;
+use rosc::{OscBundle, OscMessage, OscPacket, OscType};
+use sysinfo::{Pid, System};
+use transport::Transport;
+use workers::Workers;
+
+/// How long side threads get to finish when shutting down.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The gap between the messages of one tick with `--split-messages`, so the
+/// chatbox doesn't drop any of them.
+const SPLIT_MESSAGE_SPACING: Duration = Duration::from_millis(1500);
+
+/// The longest wait between two tries of one send.
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// A tick that took this many times longer than planned means the system
+/// was asleep in between.
+const SLEEP_FACTOR: u32 = 3;
+
+static NVML_INSTANCE: Lazy<Result<Nvml, NvmlError>> = Lazy::new(Nvml::init);
+
+/// Makes sure "no supported gpu" is said once, not on every reload.
+static NO_GPU_LOGGED: Once = Once::new();
+
+/// Whether the battery line's automatic decision was logged already.
+static BATTERY_LOGGED: Once = Once::new();
+
+/// A tiny xorshift generator, only used to spread out sends.
+struct Rng(u64);
+
+impl Rng {
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        // the state must never be zero
+        Self((nanos ^ (u64::from(std::process::id()) << 32)) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a number in `0..=max`.
+    pub fn up_to(&mut self, max: u64) -> u64 {
+        match max.checked_add(1) {
+            Some(bound) => self.next_u64() % bound,
+            None => self.next_u64(),
+        }
+    }
+}
+
+/// Counts what happened to outgoing messages over the whole session.
+#[derive(Default)]
+struct Counters {
+    sends: AtomicU64,
+    failed_sends: AtomicU64,
+    /// Sends held back by `--send-min-gap-ms`, as counted by the transport
+    throttled_sends: AtomicU64,
+}
+
+impl Counters {
+    pub fn sent(&self) {
+        self.sends.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn failed(&self) {
+        self.failed_sends.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn throttled(&self, count: u64) {
+        self.throttled_sends.store(count, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Display for Counters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sends_total={} failed_sends_total={} throttled_sends_total={}",
+            self.sends.load(Ordering::Relaxed),
+            self.failed_sends.load(Ordering::Relaxed),
+            self.throttled_sends.load(Ordering::Relaxed),
+        )
+    }
+}
 
-static NVML_INSTANCE: Lazy<Nvml> = Lazy::new(|| Nvml::init().unwrap());
+/// The highest values the providers saw since launch, and the gpu's energy
+/// counter at launch and now.
+#[derive(Default)]
+struct Peaks {
+    cpu_usage: Cell<Option<f32>>,
+    gpu_temperature: Cell<Option<u32>>,
+    ram_used: Cell<Option<u64>>,
+    gpu_energy: Cell<Option<(u64, u64)>>,
+}
+
+impl Peaks {
+    fn raise<T: PartialOrd + Copy>(peak: &Cell<Option<T>>, value: T) {
+        if peak.get().is_none_or(|peak| value > peak) {
+            peak.set(Some(value));
+        }
+    }
+
+    pub fn cpu_usage(&self, usage: f32) {
+        Self::raise(&self.cpu_usage, usage);
+    }
+
+    pub fn gpu_temperature(&self, temperature: u32) {
+        Self::raise(&self.gpu_temperature, temperature);
+    }
+
+    pub fn ram_used(&self, used: u64) {
+        Self::raise(&self.ram_used, used);
+    }
+
+    /// Takes a reading of the gpu's energy counter in mJ, returning the
+    /// energy used since the first one.
+    pub fn gpu_energy(&self, counter: u64) -> u64 {
+        let start = self.gpu_energy.get().map_or(counter, |(start, _)| start);
+        self.gpu_energy.set(Some((start, counter)));
+        counter.saturating_sub(start)
+    }
+
+    /// e.g. `gpu=0.42Wh`, if the gpu's energy was counted.
+    pub fn energy_report(&self) -> Option<String> {
+        let (start, end) = self.gpu_energy.get()?;
+        Some(format!(
+            "gpu={:.2}Wh",
+            watt_hours(end.saturating_sub(start))
+        ))
+    }
+
+    /// e.g. `cpu_usage=98.00% gpu_temperature=80°C ram_used=12.3 GiB`, without
+    /// what was never measured.
+    pub fn report(&self, temp_unit: TempUnit) -> String {
+        let mut parts = Vec::new();
+        if let Some(usage) = self.cpu_usage.get() {
+            parts.push(format!("cpu_usage={:.2}%", usage));
+        }
+        if let Some(temperature) = self.gpu_temperature.get() {
+            parts.push(format!(
+                "gpu_temperature={:.0}°{}",
+                temp_unit.convert(temperature as f32),
+                temp_unit.symbol()
+            ));
+        }
+        if let Some(used) = self.ram_used.get() {
+            parts.push(format!("ram_used={}", bytesize::to_string(used, true)));
+        }
+        parts.join(" ")
+    }
+}
 
-#[derive(Parser, Debug)]
-#[command(version, about)]
-struct Args {
-    /// Do not show time
-    #[arg(short = 't', long)]
-    no_time: bool,
+/// `NVML_ERROR_NOT_READY`, which nvml-wrapper doesn't have a variant for.
+const NVML_ERROR_NOT_READY: u32 = 27;
 
-    /// Do not show cpu usage
-    #[arg(short = 'c', long)]
-    no_cpu: bool,
+/// How many ticks in a row the driver may be not ready before that counts
+/// against `--stale-ticks` like any other failed read.
+const NOT_READY_GRACE_TICKS: u32 = 3;
+
+#[derive(Debug)]
+enum InfoError {
+    Nvml(NvmlError),
+    Io(io::Error),
+}
+
+impl InfoError {
+    /// Whether the driver is busy right after init or during a driver event
+    /// and will likely answer on the next tick.
+    pub fn is_not_ready(&self) -> bool {
+        matches!(
+            self,
+            Self::Nvml(NvmlError::UnexpectedVariant(NVML_ERROR_NOT_READY))
+        )
+    }
+}
 
-    /// Do not show ram usage
-    #[arg(short = 'r', long)]
-    no_ram: bool,
+impl fmt::Display for InfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nvml(e) => write!(f, "{}", e),
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
 
-    /// Do not show gpu usage
-    #[arg(short = 'g', long)]
-    no_gpu: bool,
+impl From<NvmlError> for InfoError {
+    fn from(e: NvmlError) -> Self {
+        Self::Nvml(e)
+    }
+}
 
-    /// Time interval in seconds
-    #[arg(short, long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..))]
-    interval: u64,
+impl From<io::Error> for InfoError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
 }
 
+type InfoResult = Result<String, InfoError>;
+
 trait Info {
-    fn get_info(&mut self) -> String;
+    /// Short name used when reporting errors.
+    fn name(&self) -> &str;
+
+    fn get_info(&mut self) -> InfoResult;
+
+    /// An abbreviated version of `get_info` for `--compact`.
+    fn get_info_compact(&mut self) -> InfoResult {
+        self.get_info()
+    }
+
+    /// Forgets earlier samples, e.g. after the system was asleep, so that
+    /// the next read doesn't compare against them.
+    fn reset(&mut self) {}
+
+    /// Takes an extra reading between two messages, for a provider that
+    /// averages them into the next `get_info`.
+    fn sample(&mut self) {}
+
+    /// The value of `metric` as of the last `get_info`, if this provider
+    /// measures it.
+    fn metric(&self, _metric: Metric) -> Option<f32> {
+        None
+    }
+}
+
+/// Remembers the last good output of a fallible provider, so that a read
+/// that fails now and then doesn't blank its line.
+struct LastGood {
+    value: Option<String>,
+    misses: u32,
+    max_misses: u32,
+    marker: bool,
+    /// Reads in a row where the driver wasn't ready
+    not_ready: u32,
+}
+
+impl LastGood {
+    pub fn new(max_misses: u32, marker: bool) -> Self {
+        Self {
+            value: None,
+            misses: 0,
+            max_misses,
+            marker,
+            not_ready: 0,
+        }
+    }
+
+    /// Counts reads where the driver isn't ready, giving whether this one is
+    /// excused: within `NOT_READY_GRACE_TICKS` in a row, it isn't a miss.
+    pub fn excuse<T>(&mut self, result: &Result<T, InfoError>) -> bool {
+        match result {
+            Err(e) if e.is_not_ready() => {
+                self.not_ready = self.not_ready.saturating_add(1);
+                self.not_ready <= NOT_READY_GRACE_TICKS
+            }
+            _ => {
+                self.not_ready = 0;
+                false
+            }
+        }
+    }
+
+    /// Whether the last result `resolve` got was excused, see `excuse`.
+    pub fn excused(&self) -> bool {
+        (1..=NOT_READY_GRACE_TICKS).contains(&self.not_ready)
+    }
+
+    /// Remembers `Ok` values; on `Err`, gives back the last good value
+    /// (prefixed with `~` if `marker` is set) for up to `max_misses` ticks.
+    /// An excused not ready driver gets the last value as it was.
+    pub fn resolve(&mut self, name: &str, result: InfoResult) -> InfoResult {
+        if self.excuse(&result) {
+            if let Some(value) = &self.value {
+                return Ok(value.clone());
+            }
+        }
+        match result {
+            Ok(value) => {
+                self.misses = 0;
+                self.value = Some(value.clone());
+                Ok(value)
+            }
+            Err(e) => {
+                self.misses = self.misses.saturating_add(1);
+                match &self.value {
+                    Some(value) if self.misses <= self.max_misses => {
+                        eprintln!("{}: {}, showing the last value", name, e);
+                        Ok(if self.marker {
+                            format!("~{}", value)
+                        } else {
+                            value.clone()
+                        })
+                    }
+                    _ => Err(e),
+                }
+            }
+        }
+    }
 }
 
-struct TimeInfo;
+struct TimeInfo {
+    format: &'static str,
+}
+
+impl TimeInfo {
+    pub fn new(format: &'static str) -> Self {
+        Self { format }
+    }
+}
 
 impl Info for TimeInfo {
-    fn get_info(&mut self) -> String {
-        format!(
-            "{}",
-            chrono::Local::now().format("%m/%d/%Y %H:%M:%S UTC%:::z")
-        )
+    fn name(&self) -> &str {
+        "time"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        Ok(format!("{}", chrono::Local::now().format(self.format)))
+    }
+
+    fn get_info_compact(&mut self) -> InfoResult {
+        Ok(chrono::Local::now().format("%H:%M").to_string())
     }
 }
 
 struct CpuInfo {
     sys: Rc<RefCell<System>>,
+    temperature: Option<sensor::CpuTemperature>,
+    show_delta: bool,
+    last_usage: Option<f32>,
+    last_temperature: Option<f32>,
+    times: Option<CpuTimes>,
+    last_breakdown: Option<(f32, f32)>,
+    /// Readings taken by `sample` since the last message
+    samples: Vec<f32>,
+    temp_unit: TempUnit,
+    peaks: Rc<Peaks>,
+    labels: Rc<Labels>,
 }
 
 impl CpuInfo {
-    pub fn new(sys: Rc<RefCell<System>>) -> Self {
-        Self { sys }
+    pub fn new(
+        sys: Rc<RefCell<System>>,
+        temperature: Option<sensor::CpuTemperature>,
+        show_delta: bool,
+        breakdown: bool,
+        temp_unit: TempUnit,
+        peaks: Rc<Peaks>,
+        labels: Rc<Labels>,
+    ) -> Self {
+        let times = breakdown.then(|| {
+            let mut times = CpuTimes::default();
+            // the first read only sets the baseline
+            times.read();
+            times
+        });
+        Self {
+            sys,
+            temperature,
+            show_delta,
+            last_usage: None,
+            last_temperature: None,
+            times,
+            last_breakdown: None,
+            samples: Vec::new(),
+            temp_unit,
+            peaks,
+            labels,
+        }
     }
-}
 
-impl Info for CpuInfo {
-    fn get_info(&mut self) -> String {
+    /// Refreshes the usage and temperature, returning the previous usage.
+    fn refresh(&mut self) -> Option<f32> {
         self.sys.borrow_mut().refresh_cpu();
         self.sys.borrow_mut().refresh_processes();
-        format!(
-            "CPU: {:.2}%, Processes: {:?}",
-            self.sys.borrow().global_cpu_info().cpu_usage(),
+        let mut usage = self.sys.borrow().global_cpu_info().cpu_usage();
+        if !self.samples.is_empty() {
+            self.samples.push(usage);
+            usage = self.samples.iter().sum::<f32>() / self.samples.len() as f32;
+            self.samples.clear();
+        }
+        self.last_temperature = self.temperature.as_mut().and_then(|t| t.read());
+        self.last_breakdown = self.times.as_mut().and_then(|t| t.read());
+        self.peaks.cpu_usage(usage);
+        self.last_usage.replace(usage)
+    }
+}
+
+impl Info for CpuInfo {
+    fn name(&self) -> &str {
+        "cpu"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        let last_usage = self.refresh();
+        let usage = self.last_usage.unwrap_or_default();
+        Ok(format!(
+            "{}: {}{}{}, {}: {:?}",
+            self.labels.get(Label::Cpu),
+            match self.last_breakdown {
+                Some((user, system)) => format!(
+                    "usr {} sys {}",
+                    self.labels.percent(user, 0),
+                    self.labels.percent(system, 0)
+                ),
+                None => self.labels.percent(usage, 2),
+            },
+            match last_usage.filter(|_| self.show_delta) {
+                Some(last) => format!(
+                    " ({}{})",
+                    if usage >= last { "+" } else { "" },
+                    self.labels.number(usage - last, 2)
+                ),
+                None => "".to_string(),
+            },
+            match self.last_temperature {
+                Some(temp) => format!(
+                    " ({})",
+                    self.labels.temperature(temp, self.temp_unit, false)
+                ),
+                None => "".to_string(),
+            },
+            self.labels.get(Label::Processes),
             self.sys.borrow().processes().len(),
-        )
+        ))
+    }
+
+    fn reset(&mut self) {
+        self.last_usage = None;
+        self.samples.clear();
+    }
+
+    fn sample(&mut self) {
+        self.sys.borrow_mut().refresh_cpu();
+        self.samples
+            .push(self.sys.borrow().global_cpu_info().cpu_usage());
+    }
+
+    fn get_info_compact(&mut self) -> InfoResult {
+        self.refresh();
+        Ok(format!(
+            "{}{}{}",
+            self.labels.get(Label::Cpu),
+            self.labels.percent(self.last_usage.unwrap_or_default(), 0),
+            match self.last_temperature {
+                Some(temp) => format!(" {}", self.labels.temperature(temp, self.temp_unit, true)),
+                None => "".to_string(),
+            },
+        ))
+    }
+
+    fn metric(&self, metric: Metric) -> Option<f32> {
+        match metric {
+            Metric::CpuUsage => self.last_usage,
+            Metric::CpuTemp => self.last_temperature,
+            _ => None,
+        }
     }
 }
 
 struct RamInfo {
     sys: Rc<RefCell<System>>,
+    format: RamFormat,
+    mode: RamMode,
+    show_delta: bool,
+    last_used: Option<u64>,
+    last_percent: Option<f32>,
+    bar_width: Option<u64>,
+    peaks: Rc<Peaks>,
+    labels: Rc<Labels>,
 }
 
 impl RamInfo {
-    pub fn new(sys: Rc<RefCell<System>>) -> Self {
-        Self { sys }
+    pub fn new(
+        sys: Rc<RefCell<System>>,
+        format: RamFormat,
+        mode: RamMode,
+        show_delta: bool,
+        bar_width: Option<u64>,
+        peaks: Rc<Peaks>,
+        labels: Rc<Labels>,
+    ) -> Self {
+        Self {
+            sys,
+            format,
+            mode: mode.resolve(),
+            show_delta,
+            last_used: None,
+            last_percent: None,
+            bar_width,
+            peaks,
+            labels,
+        }
+    }
+
+    /// Refreshes the memory, returning the used and total memory and the
+    /// previously used memory.
+    fn refresh(&mut self) -> (u64, u64, Option<u64>) {
+        self.sys.borrow_mut().refresh_memory();
+        let total = self.sys.borrow().total_memory();
+        let used = match self.mode {
+            RamMode::Available => total.saturating_sub(self.sys.borrow().available_memory()),
+            _ => self.sys.borrow().used_memory(),
+        };
+        self.last_percent = Some(used as f32 / total as f32 * 100.0);
+        self.peaks.ram_used(used);
+        (used, total, self.last_used.replace(used))
     }
 }
 
 impl Info for RamInfo {
-    fn get_info(&mut self) -> String {
-        self.sys.borrow_mut().refresh_memory();
-        format!(
-            "RAM: {} ({:.2}%)",
-            bytesize::to_string(self.sys.borrow().used_memory(), true),
-            self.sys.borrow().used_memory() as f32 / self.sys.borrow().total_memory() as f32
-                * 100.0
-        )
+    fn name(&self) -> &str {
+        "ram"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        let (used, total, last_used) = self.refresh();
+        let percent = self.last_percent.unwrap_or_default();
+        let delta = match last_used.filter(|_| self.show_delta) {
+            Some(last) if used >= last => format!(" (+{})", self.labels.size(used - last)),
+            Some(last) => format!(" (-{})", self.labels.size(last - used)),
+            None => "".to_string(),
+        };
+        let label = self.labels.get(Label::Ram);
+        let info = match (self.bar_width, self.format) {
+            (Some(width), _) => format!("{}: {}", label, self.labels.bar(percent, width)),
+            (None, RamFormat::Pct) => format!("{}: {}", label, self.labels.percent(percent, 2)),
+            (None, RamFormat::Abs) => format!("{}: {}", label, self.labels.size(used)),
+            (None, RamFormat::AbsTotal) => format!(
+                "{}: {}/{}",
+                label,
+                self.labels.size(used),
+                self.labels.size(total),
+            ),
+            (None, RamFormat::Both) => format!(
+                "{}: {} ({})",
+                label,
+                self.labels.size(used),
+                self.labels.percent(percent, 2)
+            ),
+        };
+        Ok(info + &delta)
+    }
+
+    fn reset(&mut self) {
+        self.last_used = None;
+    }
+
+    fn get_info_compact(&mut self) -> InfoResult {
+        self.refresh();
+        let percent = self.last_percent.unwrap_or_default();
+        Ok(match self.bar_width {
+            Some(width) => format!(
+                "{}{}",
+                self.labels.get(Label::Ram),
+                self.labels.bar(percent, width)
+            ),
+            None => format!(
+                "{}{}",
+                self.labels.get(Label::Ram),
+                self.labels.percent(percent, 0)
+            ),
+        })
+    }
+
+    fn metric(&self, metric: Metric) -> Option<f32> {
+        match metric {
+            Metric::RamUsage => self.last_percent,
+            _ => None,
+        }
+    }
+}
+
+struct ProcessInfo {
+    sys: Rc<RefCell<System>>,
+    name: String,
+    /// `name` as shown while nothing matches
+    shown_name: String,
+    max_name: usize,
+    labels: Rc<Labels>,
+}
+
+/// What the processes matching a `ProcessInfo` use together.
+struct ProcessUsage {
+    /// The name they share as shown, or the one searched for if they differ
+    name: String,
+    cpu_usage: f32,
+    memory: u64,
+}
+
+impl ProcessInfo {
+    pub fn new(
+        sys: Rc<RefCell<System>>,
+        name: String,
+        max_name: usize,
+        labels: Rc<Labels>,
+    ) -> Self {
+        Self {
+            shown_name: format::process_name(&name, max_name),
+            sys,
+            name,
+            max_name,
+            labels,
+        }
+    }
+
+    /// The combined cpu usage and memory of the matching processes, if any
+    /// are running.
+    fn usage(&self) -> Option<ProcessUsage> {
+        self.sys.borrow_mut().refresh_processes();
+        let sys = self.sys.borrow();
+        let matches = sys.processes_by_name(&self.name).map(|process| {
+            (
+                process.pid(),
+                process.name(),
+                process.cpu_usage(),
+                process.memory(),
+                process.tasks(),
+            )
+        });
+        ProcessUsage::add_up(matches, &self.shown_name, self.max_name)
+    }
+}
+
+/// A process as `ProcessUsage::add_up` sees it: its pid, name, cpu usage,
+/// memory and the pids of its threads.
+type ProcessEntry<'a> = (Pid, &'a str, f32, u64, Option<&'a HashSet<Pid>>);
+
+impl ProcessUsage {
+    /// Adds up `processes`, or gives `None` if there are none. `shown_name`
+    /// stands in for names that differ, and a single name is made safe to
+    /// show with at most `max_name` characters.
+    fn add_up<'a>(
+        processes: impl IntoIterator<Item = ProcessEntry<'a>>,
+        shown_name: &str,
+        max_name: usize,
+    ) -> Option<Self> {
+        let processes: Vec<_> = processes.into_iter().collect();
+        // on linux, threads are listed as processes too; don't count them twice
+        let threads: HashSet<Pid> = processes
+            .iter()
+            .filter_map(|(_, _, _, _, tasks)| *tasks)
+            .flatten()
+            .copied()
+            .collect();
+
+        let mut names = HashSet::new();
+        let mut cpu_usage = 0.0;
+        let mut memory = 0;
+        for (_, name, cpu, mem, _) in processes
+            .into_iter()
+            .filter(|(pid, ..)| !threads.contains(pid))
+        {
+            names.insert(name);
+            cpu_usage += cpu;
+            memory += mem;
+        }
+
+        // a name from the process table may be as long and odd as it likes
+        let name = match names.len() {
+            0 => return None,
+            1 => format::process_name(names.into_iter().next().unwrap(), max_name),
+            _ => shown_name.to_string(),
+        };
+        Some(Self {
+            name,
+            cpu_usage,
+            memory,
+        })
+    }
+}
+
+impl Info for ProcessInfo {
+    fn name(&self) -> &str {
+        "watch"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        Ok(match self.usage() {
+            Some(usage) => format!(
+                "{}: {}: {}, {}: {}",
+                usage.name,
+                self.labels.get(Label::Cpu),
+                self.labels.percent(usage.cpu_usage, 2),
+                self.labels.get(Label::Ram),
+                self.labels.size(usage.memory),
+            ),
+            None => "watch: not running".to_string(),
+        })
+    }
+
+    fn get_info_compact(&mut self) -> InfoResult {
+        Ok(match self.usage() {
+            Some(usage) => format!("{} {}", usage.name, self.labels.percent(usage.cpu_usage, 0)),
+            None => format!("{} off", self.shown_name),
+        })
+    }
+}
+
+struct UptimeInfo {
+    format: format::DurationFormat,
+    labels: Rc<Labels>,
+}
+
+impl UptimeInfo {
+    pub fn new(format: format::DurationFormat, labels: Rc<Labels>) -> Self {
+        Self { format, labels }
+    }
+}
+
+impl Info for UptimeInfo {
+    fn name(&self) -> &str {
+        "uptime"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        Ok(format!(
+            "{}: {}",
+            self.labels.get(Label::Uptime),
+            format::format_duration(System::uptime(), &self.format)
+        ))
+    }
+
+    fn get_info_compact(&mut self) -> InfoResult {
+        Ok(format!(
+            "{} {}",
+            self.labels.get(Label::Uptime),
+            format::format_duration(System::uptime(), &self.format)
+        ))
+    }
+}
+
+/// The memory used by this program.
+struct SelfInfo {
+    sys: Rc<RefCell<System>>,
+    pid: Option<Pid>,
+    labels: Rc<Labels>,
+}
+
+impl SelfInfo {
+    pub fn new(sys: Rc<RefCell<System>>, labels: Rc<Labels>) -> Self {
+        Self {
+            sys,
+            pid: sysinfo::get_current_pid().ok(),
+            labels,
+        }
+    }
+}
+
+impl Info for SelfInfo {
+    fn name(&self) -> &str {
+        "self"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        let label = self.labels.get(Label::SelfUsage);
+        let Some(pid) = self.pid else {
+            return Ok(format!("{}: --", label));
+        };
+        self.sys.borrow_mut().refresh_process(pid);
+        Ok(match self.sys.borrow().process(pid) {
+            Some(process) => format!("{}: {}", label, self.labels.size(process.memory())),
+            None => format!("{}: --", label),
+        })
+    }
+}
+
+/// Shows the next of a list of lines every time.
+struct QuipInfo {
+    quips: Vec<String>,
+    next: usize,
+}
+
+impl QuipInfo {
+    pub fn new(quips: Vec<String>) -> Self {
+        Self { quips, next: 0 }
+    }
+}
+
+impl Info for QuipInfo {
+    fn name(&self) -> &str {
+        "quips"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        let quip = self.quips[self.next % self.quips.len()].clone();
+        self.next = (self.next + 1) % self.quips.len();
+        Ok(quip)
+    }
+}
+
+/// One successful read of the gpu.
+struct GpuSample {
+    usage: u32,
+    power: f32,
+    temperature: Option<u32>,
+    memory_used: u64,
+    memory_percent: f32,
+    video_clock: Option<u32>,
+    pstate: Option<u8>,
+    memory_util: Option<u32>,
+    /// In mJ since launch
+    energy: Option<u64>,
+    temperature_trend: Option<char>,
+}
+
+/// What the gpu line shows besides usage, power, temperature and vram.
+#[derive(Clone, Copy, Debug)]
+struct GpuOptions {
+    video_clock: bool,
+    pstate: bool,
+    mem_util: bool,
+    energy: bool,
+    vram_only: bool,
+    temp_trend: bool,
+    temp_unit: TempUnit,
+    vram_bar: Option<u64>,
+}
+
+fn watt_hours(millijoules: u64) -> f64 {
+    millijoules as f64 / 3_600_000.0
+}
+
+/// The nvml calls behind the gpu line, so that tests can stand in for a gpu.
+trait GpuReader {
+    fn memory_info(&self) -> Result<MemoryInfo, NvmlError>;
+    fn temperature(&self) -> Result<u32, NvmlError>;
+    fn utilization_rates(&self) -> Result<Utilization, NvmlError>;
+    /// In mW
+    fn power_usage(&self) -> Result<u32, NvmlError>;
+    /// In MHz
+    fn video_clock(&self) -> Result<u32, NvmlError>;
+    fn performance_state(&self) -> Result<PerformanceState, NvmlError>;
+    /// In mJ since the driver was loaded
+    fn total_energy_consumption(&self) -> Result<u64, NvmlError>;
+}
+
+impl GpuReader for Device<'_> {
+    fn memory_info(&self) -> Result<MemoryInfo, NvmlError> {
+        Device::memory_info(self)
+    }
+
+    fn temperature(&self) -> Result<u32, NvmlError> {
+        Device::temperature(self, TemperatureSensor::Gpu)
+    }
+
+    fn utilization_rates(&self) -> Result<Utilization, NvmlError> {
+        Device::utilization_rates(self)
+    }
+
+    fn power_usage(&self) -> Result<u32, NvmlError> {
+        Device::power_usage(self)
+    }
+
+    fn video_clock(&self) -> Result<u32, NvmlError> {
+        Device::clock_info(self, Clock::Video)
+    }
+
+    fn performance_state(&self) -> Result<PerformanceState, NvmlError> {
+        Device::performance_state(self)
+    }
+
+    fn total_energy_consumption(&self) -> Result<u64, NvmlError> {
+        Device::total_energy_consumption(self)
     }
 }
 
 struct GpuInfo<'a> {
-    device: Box<Device<'a>>,
+    device: Box<dyn GpuReader + 'a>,
+    options: GpuOptions,
+    last_good: LastGood,
+    sample: Option<GpuSample>,
+    last_temperature: Option<u32>,
+    peaks: Rc<Peaks>,
+    labels: Rc<Labels>,
 }
 
 impl<'a> GpuInfo<'a> {
-    pub fn new() -> Self {
+    pub fn new(
+        nvml: &'a Nvml,
+        index: u32,
+        options: GpuOptions,
+        last_good: LastGood,
+        peaks: Rc<Peaks>,
+        labels: Rc<Labels>,
+    ) -> Result<Self, NvmlError> {
+        let device = nvml.device_by_index(index)?;
+        Ok(Self::with_reader(
+            Box::new(device),
+            options,
+            last_good,
+            peaks,
+            labels,
+        ))
+    }
+
+    fn with_reader(
+        device: Box<dyn GpuReader + 'a>,
+        mut options: GpuOptions,
+        last_good: LastGood,
+        peaks: Rc<Peaks>,
+        labels: Rc<Labels>,
+    ) -> Self {
+        if options.energy {
+            if let Err(e) = device.total_energy_consumption() {
+                eprintln!("gpu: energy counter unavailable: {}", e);
+                options.energy = false;
+            }
+        }
         Self {
-            device: Box::new(NVML_INSTANCE.device_by_index(0).unwrap()),
+            device,
+            options,
+            last_good,
+            sample: None,
+            last_temperature: None,
+            peaks,
+            labels,
         }
     }
+
+    /// Just the vram line, e.g. `VRAM: 14.2 GiB/24.0 GiB (59%)`, or `VRAM59%`
+    /// when compact.
+    fn read_vram(&self, compact: bool) -> InfoResult {
+        let mem_info = self.device.memory_info()?;
+        let percent = mem_info.used as f32 / mem_info.total as f32 * 100.0;
+        if compact {
+            return Ok(format!(
+                "{}{}",
+                self.labels.get(Label::Vram),
+                self.labels.percent(percent, 0)
+            ));
+        }
+        Ok(format!(
+            "{}: {}/{} {}",
+            self.labels.get(Label::Vram),
+            self.labels.size(mem_info.used),
+            self.labels.size(mem_info.total),
+            match self.options.vram_bar {
+                Some(width) => self.labels.bar(percent, width),
+                None => format!("({})", self.labels.percent(percent, 0)),
+            },
+        ))
+    }
+
+    /// The gpu line, with the last good one standing in for failed reads.
+    fn line(&mut self, compact: bool) -> InfoResult {
+        let result = if self.options.vram_only {
+            self.read_vram(compact)
+        } else {
+            match self.read() {
+                Ok(sample) => {
+                    let info = if compact {
+                        sample.format_compact(&self.labels, &self.options)
+                    } else {
+                        sample.format(&self.labels, &self.options)
+                    };
+                    self.sample = Some(sample);
+                    Ok(info)
+                }
+                Err(e) => Err(InfoError::from(e)),
+            }
+        };
+        let failed = result.is_err();
+        let line = self.last_good.resolve("gpu", result);
+        // the parameters keep the last sample while the driver isn't ready
+        if failed && !self.last_good.excused() {
+            self.sample = None;
+        }
+        line.or_else(|e| {
+            eprintln!("gpu: {}", e);
+            let label = self.labels.get(Label::Gpu);
+            Ok(if compact {
+                format!("{} N/A", label)
+            } else {
+                format!("{}: N/A", label)
+            })
+        })
+    }
+
+    fn read(&mut self) -> Result<GpuSample, NvmlError> {
+        let mem_info = self.device.memory_info()?;
+        let temperature = self.device.temperature().ok();
+        let last_temperature = std::mem::replace(&mut self.last_temperature, temperature);
+        if let Some(temperature) = temperature {
+            self.peaks.gpu_temperature(temperature);
+        }
+        let utilization = self.device.utilization_rates()?;
+        Ok(GpuSample {
+            usage: utilization.gpu,
+            power: self.device.power_usage()? as f32 / 1000.0,
+            temperature,
+            memory_used: mem_info.used,
+            memory_percent: mem_info.used as f32 / mem_info.total as f32 * 100.0,
+            video_clock: self
+                .options
+                .video_clock
+                .then(|| self.device.video_clock().ok())
+                .flatten(),
+            pstate: self
+                .options
+                .pstate
+                .then(|| self.device.performance_state().ok())
+                .flatten()
+                .and_then(pstate_number),
+            memory_util: self.options.mem_util.then_some(utilization.memory),
+            energy: self
+                .options
+                .energy
+                .then(|| self.device.total_energy_consumption().ok())
+                .flatten()
+                .map(|counter| self.peaks.gpu_energy(counter)),
+            temperature_trend: match (last_temperature, temperature) {
+                (Some(last), Some(temp)) if self.options.temp_trend => {
+                    Some(match temp.cmp(&last) {
+                        std::cmp::Ordering::Greater => '↑',
+                        std::cmp::Ordering::Less => '↓',
+                        std::cmp::Ordering::Equal => '→',
+                    })
+                }
+                _ => None,
+            },
+        })
+    }
 }
 
-impl<'a> Info for GpuInfo<'a> {
-    fn get_info(&mut self) -> String {
-        let mem_info = self.device.memory_info().unwrap();
+/// The number in `P0`..`P15`, if the state is known.
+fn pstate_number(state: PerformanceState) -> Option<u8> {
+    Some(match state {
+        PerformanceState::Zero => 0,
+        PerformanceState::One => 1,
+        PerformanceState::Two => 2,
+        PerformanceState::Three => 3,
+        PerformanceState::Four => 4,
+        PerformanceState::Five => 5,
+        PerformanceState::Six => 6,
+        PerformanceState::Seven => 7,
+        PerformanceState::Eight => 8,
+        PerformanceState::Nine => 9,
+        PerformanceState::Ten => 10,
+        PerformanceState::Eleven => 11,
+        PerformanceState::Twelve => 12,
+        PerformanceState::Thirteen => 13,
+        PerformanceState::Fourteen => 14,
+        PerformanceState::Fifteen => 15,
+        PerformanceState::Unknown => return None,
+    })
+}
+
+impl GpuSample {
+    /// e.g. `GPU80% 65C`
+    fn format_compact(&self, labels: &Labels, options: &GpuOptions) -> String {
+        let label = labels.get(Label::Gpu);
+        match self.temperature {
+            Some(temp) => format!(
+                "{}{} {}",
+                label,
+                labels.percent(self.usage, 0),
+                labels.temperature(temp as f32, options.temp_unit, true)
+            ),
+            None => format!("{}{}", label, labels.percent(self.usage, 0)),
+        }
+    }
+
+    fn format(&self, labels: &Labels, options: &GpuOptions) -> String {
+        let temperature = |temp: u32| labels.temperature(temp as f32, options.temp_unit, false);
         format!(
-            "GPU: {}% ({:.2}W{})\n{} ({:.2}%)",
-            self.device.utilization_rates().unwrap().gpu,
-            self.device.power_usage().unwrap() as f32 / 1000.0,
-            match self.device.temperature(TemperatureSensor::Gpu) {
-                Ok(temp) => format!(", {}°C", temp),
-                Err(_) => "".to_string(),
+            "{}: {} ({}{}{}{}{}{}{})\n{} {}",
+            labels.get(Label::Gpu),
+            labels.percent(self.usage, 0),
+            labels.number(self.power, 2),
+            labels.unit("W"),
+            match (self.temperature, self.temperature_trend) {
+                (Some(temp), Some(trend)) => format!(", {}{}", temperature(temp), trend),
+                (Some(temp), None) => format!(", {}", temperature(temp)),
+                (None, _) => "".to_string(),
+            },
+            match self.video_clock {
+                Some(clock) => format!(
+                    ", {}: {}{}",
+                    labels.get(Label::Video),
+                    clock,
+                    labels.unit("MHz")
+                ),
+                None => "".to_string(),
+            },
+            match self.pstate {
+                Some(pstate) => format!(", {}: P{}", labels.get(Label::Pstate), pstate),
+                None => "".to_string(),
+            },
+            match self.memory_util {
+                Some(util) => format!(
+                    ", {}: {}",
+                    labels.get(Label::MemUtil),
+                    labels.percent(util, 0)
+                ),
+                None => "".to_string(),
+            },
+            match self.energy {
+                Some(energy) => format!(
+                    ", {}{}",
+                    labels.number(watt_hours(energy), 2),
+                    labels.unit("Wh")
+                ),
+                None => "".to_string(),
+            },
+            labels.size(self.memory_used),
+            match options.vram_bar {
+                Some(width) => labels.bar(self.memory_percent, width),
+                None => format!("({})", labels.percent(self.memory_percent, 2)),
             },
-            bytesize::to_string(mem_info.used, true),
-            mem_info.used as f32 / mem_info.total as f32 * 100.0,
         )
     }
 }
 
+impl<'a> Info for GpuInfo<'a> {
+    fn name(&self) -> &str {
+        "gpu"
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        self.line(false)
+    }
+
+    fn get_info_compact(&mut self) -> InfoResult {
+        self.line(true)
+    }
+
+    fn metric(&self, metric: Metric) -> Option<f32> {
+        let sample = self.sample.as_ref()?;
+        match metric {
+            Metric::GpuUsage => Some(sample.usage as f32),
+            Metric::GpuTemp => sample.temperature.map(|temp| temp as f32),
+            Metric::GpuMemory => Some(sample.memory_percent),
+            Metric::GpuPower => Some(sample.power),
+            _ => None,
+        }
+    }
+}
+
 fn main() -> io::Result<()> {
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
@@ -141,60 +1207,1382 @@ fn main() -> io::Result<()> {
     })
     .expect("Error setting Ctrl-C handler");
 
-    let args = Args::parse();
+    let mut args = Args::parse();
+    if let Some(path) = &args.config {
+        args = config::parse(path).unwrap_or_else(|e| e.exit());
+    }
+    if args.list_providers {
+        list_providers();
+        return Ok(());
+    }
+    if args.chatbox_args.first() != Some(&ChatboxArg::Text) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--chatbox-args must start with text",
+        ));
+    }
+    // only chatbox messages are recorded, parameters would be replayed as text
+    if args.output == Output::Parameters && (args.record.is_some() || args.replay.is_some()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--record and --replay only work with chatbox output",
+        ));
+    }
+    if let Some(text) = &args.test_send {
+        let transport = open_transport(&args, &running)?;
+        send_chatbox(
+            &transport,
+            &mut Vec::new(),
+            text,
+            &args.chatbox_args,
+            args.chatbox_mode,
+        )?;
+        println!("Sent: {:?} to {}", text, transport);
+        return Ok(());
+    }
+    if let Some(path) = &args.replay {
+        return replay(&args, &running, path);
+    }
+
+    match args.command.unwrap_or(Command::Run) {
+        Command::Run => run(&args, running),
+        Command::Once => once(&args, running),
+        Command::Check => check(&args),
+        Command::ListGpus => list_gpus(),
+        Command::Install => service::install(&service::baked_args("install")?),
+        Command::Uninstall => service::uninstall(),
+    }
+}
 
-    {
-        let socket = UdpSocket::bind("127.0.0.1:9001")?;
+fn run(args: &Args, running: Arc<AtomicBool>) -> io::Result<()> {
+    let mut args = args.clone();
+    #[cfg(unix)]
+    if args.config.is_some() {
+        config::reload_on_sighup();
+    }
+
+    let counters = Counters::default();
+    // outlive the providers, which are rebuilt on reload
+    let peaks = Rc::new(Peaks::default());
+    let result = {
+        let transport = open_transport(&args, &running)?;
+        let mut warned = false;
+        let mut failures_in_a_row = 0;
+        let mut result = Ok(());
         let mut buf = Vec::new();
         let sys = Rc::new(RefCell::new(System::new_all()));
+        let mut workers = Workers::new(Arc::clone(&running));
+        let mut infos = build_infos(&args, &sys, &peaks, &mut workers, &transport)?;
+        // kept apart from `workers`, which are replaced on reload
+        let mut control_workers = Workers::new(Arc::new(AtomicBool::new(true)));
+        let mut requests = open_control(&args, &mut control_workers)?.map(control::Requests::new);
+        let mut recorder = args.record.as_deref().map(Recorder::open).transpose()?;
 
-        let mut infos: Vec<Box<dyn Info>> = Vec::new();
-        if !args.no_time {
-            infos.push(Box::new(TimeInfo));
+        let mut rng = Rng::from_time();
+        let mut info = String::new();
+        loop {
+            let delay =
+                Duration::from_secs(args.interval) + Duration::from_millis(rng.up_to(args.jitter));
+            // the monotonic clock may stop while asleep, the wall clock doesn't
+            let started = SystemTime::now();
+            // each sample needs some time since the last refresh to be accurate
+            let samples = (args.cpu_samples as u128)
+                .min(delay.as_millis() / sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.as_millis())
+                .max(1) as u32;
+            let mut wake = Wake::Tick;
+            for sample in 1..=samples {
+                wake = control::wait(&running, delay / samples, requests.as_mut());
+                if !matches!(wake, Wake::Tick) {
+                    break;
+                }
+                // the last one is taken by get_info
+                if sample < samples {
+                    infos.iter_mut().for_each(|info| info.sample());
+                }
+            }
+            let request = match wake {
+                Wake::Stop => break,
+                Wake::Tick => None,
+                Wake::Request(request) => Some(request),
+            };
+            let elapsed = started.elapsed().unwrap_or_default();
+            if request.is_none() && elapsed > delay * SLEEP_FACTOR {
+                eprintln!(
+                    "{}s passed instead of {}s, the system was probably asleep; resetting deltas",
+                    elapsed.as_secs(),
+                    delay.as_secs()
+                );
+                infos.iter_mut().for_each(|info| info.reset());
+            }
+            if config::reload_requested() {
+                reload(
+                    &mut args,
+                    &sys,
+                    &peaks,
+                    &mut workers,
+                    &transport,
+                    &mut infos,
+                )?;
+            }
+            if let Some(Request::Toggle(kind, on)) = request {
+                let mut toggled = args.clone();
+                toggled.set_enabled(kind, on);
+                if rebuild(
+                    &mut args,
+                    toggled,
+                    &sys,
+                    &peaks,
+                    &mut workers,
+                    &transport,
+                    &mut infos,
+                )? {
+                    println!(
+                        "{} {}",
+                        if on { "Enabled" } else { "Disabled" },
+                        kind.to_possible_value()
+                            .map(|value| value.get_name().to_string())
+                            .unwrap_or_default()
+                    );
+                }
+            }
+            let tick_start = Instant::now();
+            if !matches!(request, Some(Request::Say(_))) {
+                get_info(&mut infos, &mut info, args.compact, args.debug_timing);
+                rewrite_info(&args, &mut info);
+            }
+            let message = match &request {
+                Some(Request::Say(text)) => text.as_str(),
+                _ if info.is_empty() => args.fallback_text.as_deref().unwrap_or_default(),
+                _ => &info,
+            };
+            let sent = if args.print_only {
+                print_message(message);
+                Ok(None)
+            } else {
+                match &request {
+                    Some(Request::Say(text)) => with_retries(&args, &running, || {
+                        send_chatbox(
+                            &transport,
+                            &mut buf,
+                            text,
+                            &args.chatbox_args,
+                            args.chatbox_mode,
+                        )
+                    })
+                    .map(|_| Some(format!("{:?}", text))),
+                    Some(Request::Send | Request::Toggle(..)) | None => {
+                        send(&args, &running, &transport, &mut buf, &infos, &info)
+                    }
+                }
+            };
+            if args.print_only || matches!(sent, Ok(Some(_))) {
+                record(&mut recorder, message);
+            }
+            if args.debug_timing {
+                eprintln!("timing: tick took {:?}", tick_start.elapsed());
+            }
+            match sent {
+                Ok(None) => {}
+                Ok(Some(sent)) => {
+                    counters.sent();
+                    failures_in_a_row = 0;
+                    println!("Sent: {}", sent);
+                }
+                Err(e) => {
+                    counters.failed();
+                    failures_in_a_row += 1;
+                    if e.kind() != io::ErrorKind::ConnectionRefused {
+                        eprintln!("send failed: {}", e);
+                    } else if !warned {
+                        eprintln!("VRChat OSC not detected on port {}", args.osc_port);
+                        warned = true;
+                    }
+                    if args.max_failures > 0 && failures_in_a_row >= args.max_failures {
+                        result = Err(io::Error::new(
+                            e.kind(),
+                            format!(
+                                "giving up after {} failed sends in a row",
+                                failures_in_a_row
+                            ),
+                        ));
+                        break;
+                    }
+                }
+            }
         }
-        if !args.no_cpu {
-            infos.push(Box::new(CpuInfo::new(Rc::clone(&sys))));
+        control_workers.shutdown(SHUTDOWN_TIMEOUT);
+        workers.shutdown(SHUTDOWN_TIMEOUT);
+        counters.throttled(transport.throttled());
+        result
+    }; // the socket is closed here
+    println!("Summary: {}", counters);
+    if args.track_peaks {
+        println!("Peaks: {}", peaks.report(args.temp_unit));
+    }
+    if let Some(energy) = peaks.energy_report() {
+        println!("Energy: {}", energy);
+    }
+    result?;
+    println!("bye");
+    Ok(())
+}
+
+/// Reads the config file again and rebuilds the providers from it, keeping
+/// the old settings if the file is broken.
+fn reload(
+    args: &mut Args,
+    sys: &Rc<RefCell<System>>,
+    peaks: &Rc<Peaks>,
+    workers: &mut Workers,
+    transport: &Transport,
+    infos: &mut Vec<Box<dyn Info>>,
+) -> io::Result<()> {
+    let Some(path) = args.config.clone() else {
+        return Ok(());
+    };
+    let mut reloaded = match config::parse(&path) {
+        Ok(reloaded) => reloaded,
+        Err(e) => {
+            eprintln!("cannot reload {}: {}", path.display(), e);
+            return Ok(());
         }
-        if !args.no_ram {
-            infos.push(Box::new(RamInfo::new(Rc::clone(&sys))));
+    };
+    // providers turned on or off over the control socket stay that way
+    reloaded.toggled = args.toggled.clone();
+    if rebuild(args, reloaded, sys, peaks, workers, transport, infos)? {
+        println!("Reloaded {}", path.display());
+    }
+    Ok(())
+}
+
+/// Switches to the `next` options and rebuilds the providers from them,
+/// keeping the current ones if that fails. Gives whether it switched.
+fn rebuild(
+    args: &mut Args,
+    next: Args,
+    sys: &Rc<RefCell<System>>,
+    peaks: &Rc<Peaks>,
+    workers: &mut Workers,
+    transport: &Transport,
+    infos: &mut Vec<Box<dyn Info>>,
+) -> io::Result<bool> {
+    // the old side threads may still hold the listen port
+    std::mem::replace(workers, Workers::new(Arc::new(AtomicBool::new(true))))
+        .shutdown(SHUTDOWN_TIMEOUT);
+    match build_infos(&next, sys, peaks, workers, transport) {
+        Ok(next_infos) => {
+            *infos = next_infos;
+            *args = next;
+            Ok(true)
         }
-        if !args.no_gpu {
-            infos.push(Box::new(GpuInfo::new()));
+        Err(e) => {
+            eprintln!("cannot rebuild the providers, keeping the old ones: {}", e);
+            *infos = build_infos(args, sys, peaks, workers, transport)?;
+            Ok(false)
         }
+    }
+}
 
-        while running.load(Ordering::SeqCst) {
-            thread::sleep(Duration::from_secs(args.interval));
-            let info = get_info(&mut infos);
-            if info.is_empty() {
-                continue;
-            }
-            let msg = OscMessage {
-                addr: "/chatbox/input".to_string(),
-                args: vec![rosc::OscType::String(info.clone()), OscType::Bool(true)],
-            };
-            let packet = OscPacket::Message(msg);
-            rosc::encoder::encode_into(&packet, &mut buf).unwrap();
+fn once(args: &Args, running: Arc<AtomicBool>) -> io::Result<()> {
+    let transport = open_transport(args, &running)?;
+    let mut buf = Vec::new();
+    let sys = Rc::new(RefCell::new(System::new_all()));
+    // stopping the workers before sending mustn't stop the send
+    let mut workers = Workers::new(Arc::new(AtomicBool::new(true)));
+    let peaks = Rc::new(Peaks::default());
+    let mut infos = build_infos(args, &sys, &peaks, &mut workers, &transport)?;
+
+    // cpu usage is measured between two refreshes
+    thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    let mut info = String::new();
+    get_info(&mut infos, &mut info, args.compact, args.debug_timing);
+    rewrite_info(args, &mut info);
+    workers.shutdown(SHUTDOWN_TIMEOUT);
+    let message = match &args.fallback_text {
+        Some(fallback) if info.is_empty() => fallback,
+        _ => &info,
+    };
+    let mut recorder = args.record.as_deref().map(Recorder::open).transpose()?;
+    if args.print_only {
+        print_message(message);
+        record(&mut recorder, message);
+    } else if let Some(sent) = send(args, &running, &transport, &mut buf, &infos, &info)? {
+        record(&mut recorder, message);
+        println!("Sent: {}", sent);
+    }
+    Ok(())
+}
+
+/// Sends the messages of a `--record` file again, waiting as long between
+/// them as when they were recorded.
+fn replay(args: &Args, running: &Arc<AtomicBool>, path: &std::path::Path) -> io::Result<()> {
+    let messages = record::read(path)?;
+    let transport = open_transport(args, running)?;
+    let mut buf = Vec::new();
+    let mut last: Option<chrono::DateTime<chrono::FixedOffset>> = None;
+    for (time, message) in messages {
+        // a clock that went back while recording doesn't wait at all
+        let gap = last.map_or(Duration::ZERO, |last| {
+            (time - last).to_std().unwrap_or_default()
+        });
+        if !workers::sleep_while(running, gap) {
+            break;
+        }
+        last = Some(time);
+        if args.print_only {
+            print_message(&message);
+            continue;
+        }
+        with_retries(args, running, || {
+            send_chatbox(
+                &transport,
+                &mut buf,
+                &message,
+                &args.chatbox_args,
+                args.chatbox_mode,
+            )
+        })?;
+        println!("Sent: {:?}", message);
+    }
+    Ok(())
+}
+
+/// Prints what `--print-only` kept from being sent.
+fn print_message(message: &str) {
+    if !message.is_empty() {
+        println!("Message: {:?}", message);
+    }
+}
+
+/// Appends `message` to the `--record` file, if there is one.
+fn record(recorder: &mut Option<Recorder>, message: &str) {
+    let Some(recorder) = recorder else {
+        return;
+    };
+    if message.is_empty() {
+        return;
+    }
+    if let Err(e) = recorder.write(message) {
+        eprintln!("cannot record the message: {}", e);
+    }
+}
+
+fn check(args: &Args) -> io::Result<()> {
+    let mut ok = true;
+    let mut report = |name: &str, result: Result<String, String>| match result {
+        Ok(detail) => println!("ok    {}: {}", name, detail),
+        Err(e) => {
+            println!("FAIL  {}: {}", name, e);
+            ok = false;
+        }
+    };
+
+    report(
+        "osc",
+        open_destination(args)
+            .map(|transport| transport.to_string())
+            .map_err(|e| e.to_string()),
+    );
+
+    let sys = System::new_all();
+    report(
+        "sysinfo",
+        match sys.cpus().len() {
+            0 => Err("no cpus found".to_string()),
+            cpus => Ok(format!(
+                "{} cpus, {} total ram",
+                cpus,
+                bytesize::to_string(sys.total_memory(), true)
+            )),
+        },
+    );
+
+    if args.enabled(ProviderKind::Gpu) {
+        report(
+            "nvml",
+            match Nvml::init().and_then(|nvml| nvml.device_count()) {
+                Ok(count) if count > 0 => Ok(format!("{} gpus", count)),
+                Ok(_) if args.gpu_expected() => Err("no nvidia gpu found".to_string()),
+                Err(e) if args.gpu_expected() => Err(e.to_string()),
+                // the gpu line is turned off then, which is fine
+                _ => Ok("off (no supported gpu)".to_string()),
+            },
+        );
+    }
 
-            socket.send_to(&buf, "127.0.0.1:9000")?;
-            println!("Sent: {:?}", info);
+    if ok {
+        Ok(())
+    } else {
+        std::process::exit(1)
+    }
+}
 
-            unsafe {
-                buf.set_len(0);
+/// Finds the first gpu whose name contains `name`, ignoring case.
+fn find_gpu(nvml: &Nvml, name: &str) -> io::Result<u32> {
+    let needle = name.to_lowercase();
+    let mut available = Vec::new();
+    for index in 0..nvml.device_count().map_err(io::Error::other)? {
+        let Ok(device_name) = nvml.device_by_index(index).and_then(|d| d.name()) else {
+            continue;
+        };
+        if device_name.to_lowercase().contains(&needle) {
+            return Ok(index);
+        }
+        available.push(format!("{}: {}", index, device_name));
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "no gpu name contains `{}`; available gpus: {}",
+            name,
+            if available.is_empty() {
+                "none".to_string()
+            } else {
+                available.join(", ")
             }
+        ),
+    ))
+}
+
+fn list_gpus() -> io::Result<()> {
+    let nvml = Nvml::init().map_err(io::Error::other)?;
+    for index in 0..nvml.device_count().map_err(io::Error::other)? {
+        match nvml.device_by_index(index).and_then(|device| device.name()) {
+            Ok(name) => println!("{}: {}", index, name),
+            Err(e) => println!("{}: {}", index, e),
         }
-    } // the socket is closed here
-    println!("bye");
+    }
     Ok(())
 }
 
-fn get_info(providers: &mut Vec<Box<dyn Info>>) -> String {
-    let mut info_str = String::new();
+fn list_providers() {
+    for kind in ProviderKind::value_variants() {
+        let Some(value) = kind.to_possible_value() else {
+            continue;
+        };
+        let availability = match provider_availability(*kind) {
+            Ok(()) => String::new(),
+            Err(reason) => format!(" ({})", reason),
+        };
+        println!(
+            "{:<8} {:<4} {}{}",
+            value.get_name(),
+            if kind.default_enabled() { "on" } else { "off" },
+            value
+                .get_help()
+                .map(|help| help.to_string())
+                .unwrap_or_default(),
+            availability,
+        );
+    }
+}
+
+/// Whether the provider can work on this system, and why not.
+fn provider_availability(kind: ProviderKind) -> Result<(), String> {
+    match kind {
+        ProviderKind::Gpu => match Nvml::init().and_then(|nvml| nvml.device_count()) {
+            Ok(0) => Err("unavailable on this system: no nvidia gpu found".to_string()),
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("unavailable on this system: {}", e)),
+        },
+        ProviderKind::Disk => Err("needs --disk".to_string()),
+        ProviderKind::Watch => Err("needs --watch-process".to_string()),
+        ProviderKind::Http => Err("needs --http-source".to_string()),
+        ProviderKind::Fps => Err("needs --fps-address".to_string()),
+        ProviderKind::Plugins => Err("needs --plugin-dir".to_string()),
+        ProviderKind::Quips => Err("needs --quips".to_string()),
+        ProviderKind::Battery if battery::read().is_none() => {
+            Err("unavailable on this system: no battery found".to_string())
+        }
+        ProviderKind::Idle if !idle::SUPPORTED => {
+            Err("unavailable on this system: only supported on Windows".to_string())
+        }
+        ProviderKind::Time
+        | ProviderKind::Cpu
+        | ProviderKind::Ram
+        | ProviderKind::Uptime
+        | ProviderKind::SelfUsage
+        | ProviderKind::Idle
+        | ProviderKind::Battery => Ok(()),
+    }
+}
+
+/// Starts listening on `--control-socket`, if given.
+#[cfg(any(unix, windows))]
+fn open_control(args: &Args, workers: &mut Workers) -> io::Result<Option<mpsc::Receiver<Request>>> {
+    match &args.control_socket {
+        Some(path) => control::listen(path, workers).map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn open_control(
+    _args: &Args,
+    _workers: &mut Workers,
+) -> io::Result<Option<mpsc::Receiver<Request>>> {
+    Ok(None)
+}
+
+fn open_transport(args: &Args, running: &Arc<AtomicBool>) -> io::Result<Transport> {
+    Ok(open_destination(args)?.with_min_gap(
+        Duration::from_millis(args.send_min_gap_ms),
+        Arc::clone(running),
+    ))
+}
+
+fn open_destination(args: &Args) -> io::Result<Transport> {
+    #[cfg(unix)]
+    if let Some(path) = &args.osc_unix {
+        return Transport::unix(path);
+    }
+    match args.osc_transport {
+        OscTransport::Udp => Transport::udp(
+            &args.osc_host,
+            args.osc_port,
+            args.warn_if_no_listener,
+            args.bind_source,
+        ),
+        OscTransport::Tcp if args.bind_source.is_some() => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--bind-source only works with udp",
+        )),
+        OscTransport::Tcp => Transport::tcp(&args.osc_host, args.osc_port),
+    }
+}
+
+fn build_infos(
+    args: &Args,
+    sys: &Rc<RefCell<System>>,
+    peaks: &Rc<Peaks>,
+    workers: &mut Workers,
+    transport: &Transport,
+) -> io::Result<Vec<Box<dyn Info>>> {
+    if args.output == Output::Parameters {
+        if args.param.is_empty() && args.level.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "parameters output needs at least one --param or --level",
+            ));
+        }
+        if let Some((metric, address)) = args
+            .param
+            .iter()
+            .map(|p| (p.metric, &p.address))
+            .chain(args.level.iter().map(|l| (l.metric, &l.address)))
+            .find(|(metric, _)| !args.enabled(metric.provider()))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} needs the {:?} provider, which is turned off",
+                    address,
+                    metric.provider()
+                ),
+            ));
+        }
+    }
+
+    let labels =
+        Rc::new(Labels::new(args.lang, &args.label).with_numbers(args.trim_zeros, args.no_units));
+    let mut infos: Vec<Box<dyn Info>> = Vec::new();
+    if args.enabled(ProviderKind::Time) {
+        infos.push(Box::new(TimeInfo::new(labels::time_format(args.lang))));
+    }
+    if args.enabled(ProviderKind::Cpu) {
+        infos.push(Box::new(CpuInfo::new(
+            Rc::clone(sys),
+            args.cpu_sensor
+                .map(|sensor| sensor::CpuTemperature::new(sensor, workers)),
+            args.show_delta,
+            args.cpu_breakdown,
+            args.temp_unit,
+            Rc::clone(peaks),
+            Rc::clone(&labels),
+        )));
+    }
+    if args.enabled(ProviderKind::Ram) {
+        infos.push(Box::new(RamInfo::new(
+            Rc::clone(sys),
+            args.ram_format,
+            args.ram_mode,
+            args.show_delta,
+            args.ram_bar.then_some(args.bar_width),
+            Rc::clone(peaks),
+            Rc::clone(&labels),
+        )));
+    }
+    let nvml = match NVML_INSTANCE.as_ref() {
+        Ok(nvml) if nvml.device_count().is_ok_and(|count| count > 0) => Ok(nvml),
+        Ok(_) => Err("no nvidia gpu found".to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if args.enabled(ProviderKind::Gpu) && (nvml.is_ok() || args.gpu_expected()) {
+        let nvml = nvml.map_err(io::Error::other)?;
+        let index = match &args.gpu_name {
+            Some(name) => find_gpu(nvml, name)?,
+            None => 0,
+        };
+        let gpu = GpuInfo::new(
+            nvml,
+            index,
+            GpuOptions {
+                video_clock: args.gpu_video_clock,
+                pstate: args.gpu_pstate,
+                mem_util: args.gpu_mem_util,
+                energy: args.gpu_energy,
+                vram_only: args.gpu_vram_only,
+                temp_trend: args.gpu_temp_trend,
+                temp_unit: args.temp_unit,
+                vram_bar: args.vram_bar.then_some(args.bar_width),
+            },
+            LastGood::new(args.stale_ticks, args.stale_marker),
+            Rc::clone(peaks),
+            Rc::clone(&labels),
+        )
+        .map_err(io::Error::other)?;
+        infos.push(Box::new(gpu));
+    } else if args.enabled(ProviderKind::Gpu) {
+        NO_GPU_LOGGED.call_once(|| {
+            eprintln!("no supported gpu detected; gpu monitoring off (use --gpu to require one)")
+        });
+    }
+    if !args.disk.is_empty() && args.enabled(ProviderKind::Disk) {
+        infos.push(Box::new(DiskInfo::new(
+            args.disk.clone(),
+            Rc::clone(&labels),
+        )));
+    }
+    // a battery asked for with --battery or --show is shown even when missing
+    let battery_expected = args.battery
+        || args
+            .show
+            .as_ref()
+            .is_some_and(|kinds| kinds.contains(&ProviderKind::Battery));
+    if args.enabled(ProviderKind::Battery) {
+        let found = battery_expected || battery::read().is_some();
+        if !battery_expected {
+            BATTERY_LOGGED.call_once(|| {
+                if found {
+                    eprintln!("battery detected; showing it (use --no-battery to hide it)")
+                } else {
+                    eprintln!("no battery detected; battery off (use --battery to show it anyway)")
+                }
+            });
+        }
+        if found {
+            infos.push(Box::new(BatteryInfo::new(Rc::clone(&labels))));
+        }
+    }
+    if args.enabled(ProviderKind::Uptime) {
+        infos.push(Box::new(UptimeInfo::new(
+            args.uptime_format.clone(),
+            Rc::clone(&labels),
+        )));
+    }
+    if args.enabled(ProviderKind::SelfUsage) {
+        infos.push(Box::new(SelfInfo::new(Rc::clone(sys), Rc::clone(&labels))));
+    }
+    if args.enabled(ProviderKind::Idle) {
+        if idle::SUPPORTED {
+            infos.push(Box::new(UserIdleInfo::new(
+                Duration::from_secs(args.idle_after),
+                Rc::clone(&labels),
+            )));
+        } else {
+            eprintln!("idle: only supported on Windows, leaving it out");
+        }
+    }
+    if let Some(name) = args
+        .watch_process
+        .as_ref()
+        .filter(|_| args.enabled(ProviderKind::Watch))
+    {
+        infos.push(Box::new(ProcessInfo::new(
+            Rc::clone(sys),
+            name.clone(),
+            args.max_process_name as usize,
+            Rc::clone(&labels),
+        )));
+    }
+    for source in args
+        .http_source
+        .iter()
+        .filter(|_| args.enabled(ProviderKind::Http))
+    {
+        infos.push(Box::new(HttpInfo::new(source.clone(), workers)));
+    }
+    if let Some(address) = args
+        .fps_address
+        .as_ref()
+        .filter(|_| args.enabled(ProviderKind::Fps))
+    {
+        let values = transport
+            .listen_socket()
+            .and_then(|socket| listener::listen(socket, vec![address.clone()], workers))
+            .unwrap_or_else(|e| {
+                eprintln!("cannot listen for OSC messages: {}", e);
+                Default::default()
+            });
+        infos.push(Box::new(FpsInfo::new(
+            address.clone(),
+            values,
+            Rc::clone(&labels),
+        )));
+    }
+    let quips: Vec<String> = args
+        .quips
+        .iter()
+        .filter(|quip| !quip.is_empty())
+        .cloned()
+        .collect();
+    if let Some(dir) = args
+        .plugin_dir
+        .as_ref()
+        .filter(|_| args.enabled(ProviderKind::Plugins))
+    {
+        let plugins = PluginInfo::find(dir, Duration::from_millis(args.plugin_timeout_ms))
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", dir.display(), e)))?;
+        if plugins.is_empty() {
+            eprintln!("no executable plugins in {}", dir.display());
+        }
+        for plugin in plugins {
+            infos.push(Box::new(plugin));
+        }
+    }
+    if !quips.is_empty() && args.enabled(ProviderKind::Quips) {
+        infos.push(Box::new(QuipInfo::new(quips)));
+    }
+    Ok(infos)
+}
+
+/// Calls `send` until it succeeds or `--send-retries` more tries failed,
+/// waiting twice as long after each failure. Gives up early on shutdown.
+fn with_retries<T>(
+    args: &Args,
+    running: &AtomicBool,
+    mut send: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let mut backoff = Duration::from_millis(args.send_backoff_ms).min(MAX_BACKOFF);
+    let mut retries = 0;
+    loop {
+        match send() {
+            Err(e) if retries < args.send_retries => {
+                eprintln!("send failed: {}, retrying in {}ms", e, backoff.as_millis());
+                if !workers::sleep_while(running, backoff) {
+                    return Err(e);
+                }
+                retries += 1;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Sends this tick's output the way `--output` asks, each message with its own
+/// retries, returning a description of what was sent, or `None` if there was
+/// nothing to send.
+fn send(
+    args: &Args,
+    running: &AtomicBool,
+    transport: &Transport,
+    buf: &mut Vec<u8>,
+    infos: &[Box<dyn Info>],
+    info: &str,
+) -> io::Result<Option<String>> {
+    match args.output {
+        Output::Chatbox => {
+            let text = match &args.fallback_text {
+                _ if !info.is_empty() => info,
+                Some(fallback) => fallback,
+                None => return Ok(None),
+            };
+            if !args.split_messages {
+                return with_retries(args, running, || {
+                    send_chatbox(transport, buf, text, &args.chatbox_args, args.chatbox_mode)
+                })
+                .map(|_| Some(format!("{:?}", text)));
+            }
+            let mut sent = Vec::new();
+            for (i, line) in text.lines().enumerate() {
+                if i > 0 && !workers::sleep_while(running, SPLIT_MESSAGE_SPACING) {
+                    break;
+                }
+                // retried alone, so the lines before it aren't sent twice
+                with_retries(args, running, || {
+                    send_chatbox(transport, buf, line, &args.chatbox_args, args.chatbox_mode)
+                })?;
+                sent.push(format!("{:?}", line));
+            }
+            Ok(Some(sent.join(", ")))
+        }
+        Output::Parameters => with_retries(args, running, || {
+            send_parameters(
+                transport,
+                buf,
+                infos,
+                &args.param,
+                &args.level,
+                args.temp_unit,
+            )
+        }),
+    }
+}
+
+/// Sends every parameter and level whose metric has a value as one bundle.
+fn send_parameters(
+    transport: &Transport,
+    buf: &mut Vec<u8>,
+    infos: &[Box<dyn Info>],
+    params: &[Param],
+    levels: &[Level],
+    temp_unit: TempUnit,
+) -> io::Result<Option<String>> {
+    let value = |metric| infos.iter().find_map(|info| info.metric(metric));
+    let messages: Vec<OscMessage> = params
+        .iter()
+        .filter_map(|param| {
+            Some(OscMessage {
+                addr: param.address.clone(),
+                args: vec![param.to_osc(value(param.metric)?)],
+            })
+        })
+        .chain(levels.iter().filter_map(|level| {
+            Some(OscMessage {
+                addr: level.address.clone(),
+                args: vec![level.to_osc(value(level.metric)?, temp_unit)],
+            })
+        }))
+        .collect();
+    if messages.is_empty() {
+        return Ok(None);
+    }
+    let sent = messages
+        .iter()
+        .map(|msg| format!("{} {:?}", msg.addr, msg.args[0]))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let packet = OscPacket::Bundle(OscBundle {
+        // "immediately"
+        timetag: (0, 1).into(),
+        content: messages.into_iter().map(OscPacket::Message).collect(),
+    });
+    buf.clear();
+    rosc::encoder::encode_into(&packet, buf).unwrap();
+    transport.send(buf).map(|_| Some(sent))
+}
+
+fn send_chatbox(
+    transport: &Transport,
+    buf: &mut Vec<u8>,
+    info: &str,
+    schema: &[ChatboxArg],
+    mode: ChatboxMode,
+) -> io::Result<usize> {
+    let msg = OscMessage {
+        addr: "/chatbox/input".to_string(),
+        args: schema
+            .iter()
+            .map(|arg| match arg {
+                ChatboxArg::Text => OscType::String(info.to_string()),
+                ChatboxArg::Send => OscType::Bool(mode == ChatboxMode::Immediate),
+                ChatboxArg::Sfx => OscType::Bool(true),
+                ChatboxArg::NoSfx => OscType::Bool(false),
+            })
+            .collect(),
+    };
+    let packet = OscPacket::Message(msg);
+    buf.clear();
+    rosc::encoder::encode_into(&packet, buf).unwrap();
+    transport.send(buf)
+}
+
+/// Applies the options that change the whole message's text.
+fn rewrite_info(args: &Args, info: &mut String) {
+    if args.ascii && !info.is_ascii() {
+        *info = format::to_ascii(info);
+    }
+    if let Some(separator) = &args.single_line {
+        *info = info.replace('\n', separator);
+    }
+}
 
+/// Writes every provider's line into `info_str`, replacing what was there.
+/// `compact` puts the abbreviated lines on one line instead, with the time
+/// set apart from the rest.
+fn get_info(
+    providers: &mut [Box<dyn Info>],
+    info_str: &mut String,
+    compact: bool,
+    debug_timing: bool,
+) {
+    info_str.clear();
+
+    let mut separator = "";
     for provider in providers {
-        info_str.push_str(provider.get_info().as_str());
-        info_str.push_str("\n");
+        let start = Instant::now();
+        let result = if compact {
+            provider.get_info_compact()
+        } else {
+            provider.get_info()
+        };
+        if debug_timing {
+            eprintln!("timing: {} took {:?}", provider.name(), start.elapsed());
+        }
+        match result {
+            Ok(info) => {
+                info_str.push_str(info.as_str());
+                separator = match (compact, provider.name()) {
+                    (false, _) => "\n",
+                    (true, "time") => " | ",
+                    (true, _) => " ",
+                };
+                info_str.push_str(separator);
+            }
+            Err(e) => eprintln!("{}: {}", provider.name(), e),
+        }
+    }
+
+    info_str.truncate(info_str.len() - separator.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        net::{Ipv4Addr, UdpSocket},
+    };
+
+    use super::*;
+
+    fn not_ready() -> InfoResult {
+        Err(NvmlError::UnexpectedVariant(NVML_ERROR_NOT_READY).into())
+    }
+
+    #[test]
+    fn not_ready_is_told_apart_from_other_errors() {
+        assert!(InfoError::from(NvmlError::UnexpectedVariant(27)).is_not_ready());
+        assert!(!InfoError::from(NvmlError::UnexpectedVariant(26)).is_not_ready());
+        assert!(!InfoError::from(NvmlError::Unknown).is_not_ready());
+    }
+
+    #[test]
+    fn not_ready_shows_the_last_value_then_counts_as_a_miss() {
+        let mut last_good = LastGood::new(2, true);
+        assert_eq!(
+            last_good.resolve("gpu", Ok("GPU: 5%".into())).unwrap(),
+            "GPU: 5%"
+        );
+        for _ in 0..NOT_READY_GRACE_TICKS {
+            assert_eq!(last_good.resolve("gpu", not_ready()).unwrap(), "GPU: 5%");
+            assert!(last_good.excused());
+        }
+        for _ in 0..2 {
+            assert_eq!(last_good.resolve("gpu", not_ready()).unwrap(), "~GPU: 5%");
+            assert!(!last_good.excused());
+        }
+        assert!(last_good.resolve("gpu", not_ready()).is_err());
+    }
+
+    #[test]
+    fn a_good_read_starts_the_grace_period_again() {
+        let mut last_good = LastGood::new(0, false);
+        last_good.resolve("gpu", Ok("GPU: 5%".into())).unwrap();
+        for _ in 0..NOT_READY_GRACE_TICKS {
+            last_good.resolve("gpu", not_ready()).unwrap();
+        }
+        last_good.resolve("gpu", Ok("GPU: 6%".into())).unwrap();
+        assert_eq!(last_good.resolve("gpu", not_ready()).unwrap(), "GPU: 6%");
+    }
+
+    #[test]
+    fn not_ready_without_a_last_value_fails() {
+        let mut last_good = LastGood::new(3, false);
+        assert!(last_good.resolve("gpu", not_ready()).is_err());
+    }
+
+    /// A gpu at 40%, 65°C and a quarter of its vram, whose driver isn't
+    /// ready while `ready` is cleared.
+    struct FakeGpu {
+        ready: Rc<Cell<bool>>,
+    }
+
+    impl FakeGpu {
+        fn check(&self) -> Result<(), NvmlError> {
+            if self.ready.get() {
+                Ok(())
+            } else {
+                Err(NvmlError::UnexpectedVariant(NVML_ERROR_NOT_READY))
+            }
+        }
+    }
+
+    impl GpuReader for FakeGpu {
+        fn memory_info(&self) -> Result<MemoryInfo, NvmlError> {
+            self.check()?;
+            Ok(MemoryInfo {
+                free: 6 << 30,
+                total: 8 << 30,
+                used: 2 << 30,
+            })
+        }
+
+        fn temperature(&self) -> Result<u32, NvmlError> {
+            self.check().map(|_| 65)
+        }
+
+        fn utilization_rates(&self) -> Result<Utilization, NvmlError> {
+            self.check().map(|_| Utilization {
+                gpu: 40,
+                memory: 10,
+            })
+        }
+
+        fn power_usage(&self) -> Result<u32, NvmlError> {
+            self.check().map(|_| 120_000)
+        }
+
+        fn video_clock(&self) -> Result<u32, NvmlError> {
+            Err(NvmlError::NotSupported)
+        }
+
+        fn performance_state(&self) -> Result<PerformanceState, NvmlError> {
+            Err(NvmlError::NotSupported)
+        }
+
+        fn total_energy_consumption(&self) -> Result<u64, NvmlError> {
+            Err(NvmlError::NotSupported)
+        }
+    }
+
+    fn fake_gpu(vram_only: bool) -> (GpuInfo<'static>, Rc<Cell<bool>>) {
+        let ready = Rc::new(Cell::new(true));
+        let gpu = GpuInfo::with_reader(
+            Box::new(FakeGpu {
+                ready: Rc::clone(&ready),
+            }),
+            GpuOptions {
+                video_clock: false,
+                pstate: false,
+                mem_util: false,
+                energy: false,
+                vram_only,
+                temp_trend: false,
+                temp_unit: TempUnit::Celsius,
+                vram_bar: None,
+            },
+            LastGood::new(1, true),
+            Rc::new(Peaks::default()),
+            Rc::new(Labels::new(cli::Lang::En, &[])),
+        );
+        (gpu, ready)
+    }
+
+    #[test]
+    fn a_gpu_that_isnt_ready_keeps_its_sample_for_the_grace_period() {
+        let (mut gpu, ready) = fake_gpu(false);
+        let line = gpu.get_info().unwrap();
+        assert!(line.starts_with("GPU: 40% (120.00W, 65°C)"), "{}", line);
+        assert_eq!(gpu.metric(Metric::GpuUsage), Some(40.0));
+
+        ready.set(false);
+        for _ in 0..NOT_READY_GRACE_TICKS {
+            assert_eq!(gpu.get_info().unwrap(), line);
+            assert_eq!(gpu.metric(Metric::GpuUsage), Some(40.0));
+            assert_eq!(gpu.metric(Metric::GpuTemp), Some(65.0));
+        }
+        // past the grace period it is a miss like any other
+        assert_eq!(gpu.get_info().unwrap(), format!("~{}", line));
+        assert_eq!(gpu.metric(Metric::GpuUsage), None);
+        assert_eq!(gpu.get_info().unwrap(), "GPU: N/A");
+
+        ready.set(true);
+        assert_eq!(gpu.get_info().unwrap(), line);
+        assert_eq!(gpu.metric(Metric::GpuMemory), Some(25.0));
+    }
+
+    #[test]
+    fn the_compact_gpu_line_goes_through_the_last_good_value() {
+        let (mut gpu, ready) = fake_gpu(false);
+        assert_eq!(gpu.get_info_compact().unwrap(), "GPU40% 65C");
+
+        ready.set(false);
+        for _ in 0..NOT_READY_GRACE_TICKS {
+            assert_eq!(gpu.get_info_compact().unwrap(), "GPU40% 65C");
+            assert_eq!(gpu.metric(Metric::GpuUsage), Some(40.0));
+        }
+        assert_eq!(gpu.get_info_compact().unwrap(), "~GPU40% 65C");
+        assert_eq!(gpu.get_info_compact().unwrap(), "GPU N/A");
+    }
+
+    #[test]
+    fn the_compact_vram_only_line_shows_the_vram() {
+        let (mut gpu, _) = fake_gpu(true);
+        assert_eq!(gpu.get_info_compact().unwrap(), "VRAM25%");
     }
 
-    info_str.pop();
-    info_str
+    /// A provider with fixed metric values.
+    struct Fixed(Vec<(Metric, f32)>);
+
+    impl Info for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn get_info(&mut self) -> InfoResult {
+            Ok(String::new())
+        }
+
+        fn metric(&self, metric: Metric) -> Option<f32> {
+            self.0
+                .iter()
+                .find(|(m, _)| *m == metric)
+                .map(|(_, value)| *value)
+        }
+    }
+
+    /// Sends the parameters of `values` to a socket of the test's own and
+    /// decodes the bundle that arrives, by address.
+    fn round_trip(
+        values: Vec<(Metric, f32)>,
+        params: &[&str],
+        levels: &[&str],
+    ) -> HashMap<String, Vec<OscType>> {
+        let listener = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let transport =
+            Transport::udp("127.0.0.1", port, false, Some(Ipv4Addr::LOCALHOST.into())).unwrap();
+        let params: Vec<Param> = params.iter().map(|p| p.parse().unwrap()).collect();
+        let levels: Vec<Level> = levels.iter().map(|l| l.parse().unwrap()).collect();
+        let infos: Vec<Box<dyn Info>> = vec![Box::new(Fixed(values))];
+        let sent = send_parameters(
+            &transport,
+            &mut Vec::new(),
+            &infos,
+            &params,
+            &levels,
+            TempUnit::Celsius,
+        )
+        .unwrap();
+        assert!(sent.is_some(), "nothing was sent");
+
+        let mut buf = [0; rosc::decoder::MTU];
+        let (len, _) = listener.recv_from(&mut buf).unwrap();
+        let (_, packet) = rosc::decoder::decode_udp(&buf[..len]).unwrap();
+        let OscPacket::Bundle(bundle) = packet else {
+            panic!("expected a bundle, got {:?}", packet);
+        };
+        bundle
+            .content
+            .into_iter()
+            .map(|packet| match packet {
+                OscPacket::Message(msg) => (msg.addr, msg.args),
+                OscPacket::Bundle(_) => panic!("unexpected nested bundle"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parameters_decode_with_their_types_and_ranges() {
+        let messages = round_trip(
+            vec![
+                (Metric::CpuUsage, 42.4),
+                (Metric::GpuPower, 120.5),
+                (Metric::RamUsage, 61.0),
+                (Metric::GpuTemp, 85.0),
+            ],
+            &[
+                "CpuUsage:int:/avatar/parameters/CpuInt",
+                "CpuUsage:float:/avatar/parameters/CpuFloat",
+                "GpuPower:int:/avatar/parameters/GpuPower",
+                "RamUsage:bool:/avatar/parameters/RamUsed",
+            ],
+            &[
+                "GpuTemp:80:90:/avatar/parameters/GpuLevel",
+                "CpuUsage:70:90:/avatar/parameters/CpuLevel",
+            ],
+        );
+        assert_eq!(messages.len(), 6);
+        assert_eq!(messages["/avatar/parameters/CpuInt"], [OscType::Int(42)]);
+        // avatar floats range from -1 to 1, so percentages are scaled down
+        match messages["/avatar/parameters/CpuFloat"].as_slice() {
+            [OscType::Float(value)] => {
+                assert!((0.0..=1.0).contains(value));
+                assert!((value - 0.424).abs() < 1e-6);
+            }
+            other => panic!("expected one float, got {:?}", other),
+        }
+        assert_eq!(messages["/avatar/parameters/GpuPower"], [OscType::Int(121)]);
+        assert_eq!(
+            messages["/avatar/parameters/RamUsed"],
+            [OscType::Bool(true)]
+        );
+        assert_eq!(messages["/avatar/parameters/GpuLevel"], [OscType::Int(1)]);
+        assert_eq!(messages["/avatar/parameters/CpuLevel"], [OscType::Int(0)]);
+    }
+
+    #[test]
+    fn percentages_and_levels_stay_in_range_at_the_extremes() {
+        let messages = round_trip(
+            vec![(Metric::CpuUsage, 100.0), (Metric::RamUsage, 0.0)],
+            &[
+                "CpuUsage:float:/avatar/parameters/CpuFloat",
+                "RamUsage:float:/avatar/parameters/RamFloat",
+                "RamUsage:bool:/avatar/parameters/RamUsed",
+            ],
+            &[
+                "CpuUsage:70:90:/avatar/parameters/CpuLevel",
+                "RamUsage:70:90:/avatar/parameters/RamLevel",
+            ],
+        );
+        assert_eq!(
+            messages["/avatar/parameters/CpuFloat"],
+            [OscType::Float(1.0)]
+        );
+        assert_eq!(
+            messages["/avatar/parameters/RamFloat"],
+            [OscType::Float(0.0)]
+        );
+        assert_eq!(
+            messages["/avatar/parameters/RamUsed"],
+            [OscType::Bool(false)]
+        );
+        assert_eq!(messages["/avatar/parameters/CpuLevel"], [OscType::Int(2)]);
+        assert_eq!(messages["/avatar/parameters/RamLevel"], [OscType::Int(0)]);
+    }
+
+    #[test]
+    fn metrics_without_a_value_are_left_out() {
+        let messages = round_trip(
+            vec![(Metric::CpuUsage, 10.0)],
+            &[
+                "CpuUsage:int:/avatar/parameters/CpuInt",
+                "CpuTemp:int:/avatar/parameters/CpuTemp",
+            ],
+            &["GpuTemp:80:90:/avatar/parameters/GpuLevel"],
+        );
+        assert_eq!(
+            messages.keys().collect::<Vec<_>>(),
+            ["/avatar/parameters/CpuInt"]
+        );
+    }
+
+    #[test]
+    fn trimming_zeros_leaves_text_from_outside_alone() {
+        let args = Args::parse_from(["sysinfo-osc-client-vrc", "--trim-zeros"]);
+        let mut info = "build v2.10 is out\nCPU: 5.00%".to_string();
+        rewrite_info(&args, &mut info);
+        assert_eq!(info, "build v2.10 is out\nCPU: 5.00%");
+
+        let labels = Labels::new(cli::Lang::En, &[]).with_numbers(true, false);
+        assert_eq!(labels.number(5.0, 2), "5");
+        assert_eq!(labels.size(12 << 30), "12 GiB");
+        assert_eq!(labels.size(3 << 29), "1.5 GiB");
+    }
+
+    #[test]
+    fn dropping_units_leaves_text_from_outside_alone() {
+        let args = Args::parse_from(["sysinfo-osc-client-vrc", "--no-units"]);
+        let mut info = "meet at 5C, 50% off".to_string();
+        rewrite_info(&args, &mut info);
+        assert_eq!(info, "meet at 5C, 50% off");
+
+        let labels = Labels::new(cli::Lang::En, &[]).with_numbers(false, true);
+        assert_eq!(labels.percent(38.4, 2), "38.40");
+        assert_eq!(labels.size(3 << 29), "1.5 GiB");
+        for unit in [TempUnit::Celsius, TempUnit::Fahrenheit] {
+            assert_eq!(
+                labels.temperature(65.0, unit, false),
+                labels.temperature(65.0, unit, true)
+            );
+        }
+        assert_eq!(labels.temperature(65.0, TempUnit::Fahrenheit, true), "149");
+        assert_eq!(labels.bar(40.0, 5), "[██░░░] 40");
+
+        let labels = Labels::new(cli::Lang::En, &[]);
+        assert_eq!(labels.temperature(65.0, TempUnit::Celsius, false), "65°C");
+        assert_eq!(labels.temperature(65.0, TempUnit::Fahrenheit, true), "149F");
+    }
+
+    #[test]
+    fn the_gpu_line_drops_its_units() {
+        let (mut gpu, _) = fake_gpu(false);
+        gpu.labels = Rc::new(Labels::new(cli::Lang::En, &[]).with_numbers(true, true));
+        assert_eq!(gpu.get_info().unwrap(), "GPU: 40 (120, 65)\n2 GiB (25)");
+        assert_eq!(gpu.get_info_compact().unwrap(), "GPU40 65");
+    }
+
+    #[test]
+    fn a_long_odd_process_name_is_made_safe_to_show() {
+        let name = format!("\u{1b}[31m{}\n", "x".repeat(300));
+        let usage =
+            ProcessUsage::add_up([(Pid::from(1), name.as_str(), 5.0, 100, None)], "game", 24)
+                .unwrap();
+        assert_eq!(usage.name.chars().count(), 24);
+        assert!(usage.name.starts_with("?[31mxxx"), "{}", usage.name);
+        assert!(usage.name.ends_with('…'));
+        assert!(!usage.name.chars().any(char::is_control));
+    }
+
+    #[test]
+    fn processes_with_mixed_names_add_up_under_the_shown_name() {
+        let threads = HashSet::from([Pid::from(3)]);
+        let usage = ProcessUsage::add_up(
+            [
+                (Pid::from(1), "game", 10.0, 100, Some(&threads)),
+                (Pid::from(2), "game-helper", 5.0, 50, None),
+                // a thread of the first, already counted in it
+                (Pid::from(3), "game", 99.0, 999, None),
+            ],
+            "game*",
+            24,
+        )
+        .unwrap();
+        assert_eq!(usage.name, "game*");
+        assert_eq!(usage.cpu_usage, 15.0);
+        assert_eq!(usage.memory, 150);
+
+        assert!(ProcessUsage::add_up([], "game", 24).is_none());
+    }
+
+    #[test]
+    fn once_sends_every_line_of_a_split_message() {
+        let listener = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let port = listener.local_addr().unwrap().port().to_string();
+        let args = Args::parse_from([
+            "sysinfo-osc-client-vrc",
+            "--show",
+            "time,ram",
+            "--split-messages",
+            "--osc-host",
+            "127.0.0.1",
+            "--osc-port",
+            &port,
+            "--bind-source",
+            "127.0.0.1",
+        ]);
+        once(&args, Arc::new(AtomicBool::new(true))).unwrap();
+
+        let mut buf = [0; rosc::decoder::MTU];
+        let texts: Vec<String> = (0..2)
+            .map(|_| {
+                let (len, _) = listener.recv_from(&mut buf).unwrap();
+                match rosc::decoder::decode_udp(&buf[..len]).unwrap().1 {
+                    OscPacket::Message(msg) => match msg.args.first() {
+                        Some(OscType::String(text)) => text.clone(),
+                        other => panic!("expected the text first, got {:?}", other),
+                    },
+                    other => panic!("expected a message, got {:?}", other),
+                }
+            })
+            .collect();
+        assert!(texts[1].starts_with(Labels::new(cli::Lang::En, &[]).get(Label::Ram)));
+    }
 }
@@ -0,0 +1,267 @@
+use clap::ValueEnum;
+use rosc::OscType;
+
+use crate::cli::{ProviderKind, TempUnit};
+
+/// A number a provider can send as an avatar parameter.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "PascalCase")]
+pub enum Metric {
+    /// Cpu usage in percent
+    CpuUsage,
+    /// Cpu temperature in °C, needs `--cpu-sensor`
+    CpuTemp,
+    /// Ram usage in percent
+    RamUsage,
+    /// Gpu usage in percent
+    GpuUsage,
+    /// Gpu temperature in °C
+    GpuTemp,
+    /// Vram usage in percent
+    GpuMemory,
+    /// Gpu power draw in watts
+    GpuPower,
+    /// FPS reported by VRChat, needs `--fps-address`
+    Fps,
+}
+
+impl Metric {
+    /// The provider that measures this metric.
+    pub fn provider(self) -> ProviderKind {
+        match self {
+            Self::CpuUsage | Self::CpuTemp => ProviderKind::Cpu,
+            Self::RamUsage => ProviderKind::Ram,
+            Self::GpuUsage | Self::GpuTemp | Self::GpuMemory | Self::GpuPower => ProviderKind::Gpu,
+            Self::Fps => ProviderKind::Fps,
+        }
+    }
+
+    fn is_temperature(self) -> bool {
+        matches!(self, Self::CpuTemp | Self::GpuTemp)
+    }
+
+    fn is_percent(self) -> bool {
+        matches!(
+            self,
+            Self::CpuUsage | Self::RamUsage | Self::GpuUsage | Self::GpuMemory
+        )
+    }
+}
+
+/// The OSC argument type an avatar parameter expects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamType {
+    Int,
+    Float,
+    Bool,
+}
+
+/// An avatar parameter fed by a metric, parsed from `Metric:type:address`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Param {
+    pub metric: Metric,
+    pub kind: ParamType,
+    pub address: String,
+}
+
+impl Param {
+    /// Converts a metric value to the parameter's type. Avatar floats range
+    /// from -1 to 1, so percentages are sent as `0.0..=1.0` floats, while ints
+    /// keep `0..=100`; bools are true for anything above zero. Parsing keeps
+    /// floats to percentages.
+    pub fn to_osc(&self, value: f32) -> OscType {
+        match self.kind {
+            ParamType::Int => OscType::Int(value.round() as i32),
+            ParamType::Float => OscType::Float((value / 100.0).clamp(0.0, 1.0)),
+            ParamType::Bool => OscType::Bool(value > 0.0),
+        }
+    }
+}
+
+fn parse_metric(metric: &str) -> Result<Metric, String> {
+    Metric::from_str(metric, true).map_err(|_| {
+        let names: Vec<_> = Metric::value_variants()
+            .iter()
+            .filter_map(|m| m.to_possible_value())
+            .map(|v| v.get_name().to_string())
+            .collect();
+        format!(
+            "unknown metric `{}`, expected one of {}",
+            metric,
+            names.join(", ")
+        )
+    })
+}
+
+fn parse_address(address: &str) -> Result<String, String> {
+    if !address.starts_with('/') || address.contains(char::is_whitespace) {
+        return Err(format!(
+            "`{}` is not an OSC address like `/avatar/parameters/CPU`",
+            address
+        ));
+    }
+    Ok(address.to_string())
+}
+
+impl std::str::FromStr for Param {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+        let (Some(metric), Some(kind), Some(address)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format!(
+                "expected `Metric:type:address`, e.g. `CpuUsage:float:/avatar/parameters/CPU`, got `{}`",
+                s
+            ));
+        };
+        let metric = parse_metric(metric)?;
+        let kind = match kind {
+            "int" => ParamType::Int,
+            "float" => ParamType::Float,
+            "bool" => ParamType::Bool,
+            other => {
+                return Err(format!(
+                    "unknown parameter type `{}`, expected `int`, `float` or `bool`",
+                    other
+                ))
+            }
+        };
+        // VRChat would clamp e.g. 120W or 85°C to 1.0
+        if kind == ParamType::Float && !metric.is_percent() {
+            return Err(format!(
+                "{:?} is not a percentage and would not fit an avatar float, which ranges from -1 to 1; use `int` instead",
+                metric
+            ));
+        }
+        Ok(Self {
+            metric,
+            kind,
+            address: parse_address(address)?,
+        })
+    }
+}
+
+/// How bad a value is: 0 below `warn`, 1 below `crit` and 2 from `crit` on.
+pub fn classify(value: f32, warn: f32, crit: f32) -> i32 {
+    if value >= crit {
+        2
+    } else if value >= warn {
+        1
+    } else {
+        0
+    }
+}
+
+/// An int avatar parameter with the level of a metric, see `classify`,
+/// parsed from `Metric:warn:crit:address`. The thresholds of a temperature
+/// are in the `--temp-unit`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level {
+    pub metric: Metric,
+    pub warn: f32,
+    pub crit: f32,
+    pub address: String,
+}
+
+impl Level {
+    /// `value` is the metric as measured, so temperatures are in °C.
+    pub fn to_osc(&self, value: f32, unit: TempUnit) -> OscType {
+        let (warn, crit) = if self.metric.is_temperature() {
+            (unit.to_celsius(self.warn), unit.to_celsius(self.crit))
+        } else {
+            (self.warn, self.crit)
+        };
+        OscType::Int(classify(value, warn, crit))
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(4, ':');
+        let (Some(metric), Some(warn), Some(crit), Some(address)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format!(
+                "expected `Metric:warn:crit:address`, e.g. `CpuUsage:70:90:/avatar/parameters/CpuLevel`, got `{}`",
+                s
+            ));
+        };
+        let threshold = |value: &str| {
+            value
+                .parse::<f32>()
+                .map_err(|_| format!("`{}` is not a number", value))
+        };
+        let (warn, crit) = (threshold(warn)?, threshold(crit)?);
+        if warn > crit {
+            return Err(format!(
+                "the warn level {} is above the crit level {}",
+                warn, crit
+            ));
+        }
+        Ok(Self {
+            metric: parse_metric(metric)?,
+            warn,
+            crit,
+            address: parse_address(address)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(warn: f32, crit: f32) -> Level {
+        Level {
+            metric: Metric::GpuTemp,
+            warn,
+            crit,
+            address: "/avatar/parameters/GpuLevel".to_string(),
+        }
+    }
+
+    #[test]
+    fn fahrenheit_thresholds_switch_at_the_same_temperatures() {
+        let fahrenheit = level(176.0, 194.0);
+        let celsius = level(80.0, 90.0);
+        for (value, expected) in [(79.9, 0), (80.0, 1), (89.9, 1), (90.0, 2)] {
+            assert_eq!(
+                fahrenheit.to_osc(value, TempUnit::Fahrenheit),
+                OscType::Int(expected),
+                "{}°C",
+                value
+            );
+            assert_eq!(
+                celsius.to_osc(value, TempUnit::Celsius),
+                OscType::Int(expected),
+                "{}°C",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn floats_are_only_for_percentages() {
+        assert!("GpuUsage:float:/avatar/parameters/Gpu"
+            .parse::<Param>()
+            .is_ok());
+        for metric in ["GpuPower", "CpuTemp", "GpuTemp", "Fps"] {
+            let param = format!("{}:float:/avatar/parameters/X", metric);
+            assert!(param.parse::<Param>().is_err(), "{}", param);
+            let param = format!("{}:int:/avatar/parameters/X", metric);
+            assert!(param.parse::<Param>().is_ok(), "{}", param);
+        }
+    }
+
+    #[test]
+    fn only_temperature_thresholds_are_converted() {
+        let usage = Level {
+            metric: Metric::GpuUsage,
+            ..level(70.0, 90.0)
+        };
+        assert_eq!(usage.to_osc(75.0, TempUnit::Fahrenheit), OscType::Int(1));
+    }
+}
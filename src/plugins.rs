@@ -0,0 +1,101 @@
+use std::{
+    fs, io,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{Info, InfoResult};
+
+/// One executable in `--plugin-dir`, run every tick; what it prints is its
+/// line.
+pub struct PluginInfo {
+    name: String,
+    path: PathBuf,
+    timeout: Duration,
+}
+
+impl PluginInfo {
+    /// The executables in `dir`, sorted by name so their lines keep their order.
+    pub fn find(dir: &Path, timeout: Duration) -> io::Result<Vec<Self>> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| is_executable(path))
+            .collect();
+        paths.sort();
+        Ok(paths
+            .into_iter()
+            .map(|path| Self {
+                name: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                path,
+                timeout,
+            })
+            .collect())
+    }
+
+    /// Runs the plugin, killing it once it takes longer than `timeout`.
+    fn run(&self) -> io::Result<String> {
+        let mut child = Command::new(&self.path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        // read while it runs, so a chatty plugin can't fill the pipe and stall
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let reader = thread::spawn(move || {
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output).map(|_| output)
+        });
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                child.kill()?;
+                child.wait()?;
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("timed out after {:?}", self.timeout),
+                ));
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        if !status.success() {
+            return Err(io::Error::other(status.to_string()));
+        }
+        let output = reader
+            .join()
+            .map_err(|_| io::Error::other("reading the output panicked"))??;
+        Ok(String::from_utf8_lossy(&output).trim_end().to_string())
+    }
+}
+
+impl Info for PluginInfo {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_info(&mut self) -> InfoResult {
+        Ok(self.run()?)
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ["exe", "bat", "cmd"].contains(&ext.to_lowercase().as_str()))
+}
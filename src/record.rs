@@ -0,0 +1,76 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, FixedOffset, Local};
+
+/// Appends messages to a `--record` file, one per line: the time it was sent,
+/// a tab and the message with its line breaks escaped.
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    pub fn write(&mut self, message: &str) -> io::Result<()> {
+        writeln!(
+            self.file,
+            "{}\t{}",
+            Local::now().to_rfc3339(),
+            escape(message)
+        )
+    }
+}
+
+/// The messages in a `--record` file, with the times they were sent.
+pub fn read(path: &Path) -> io::Result<Vec<(DateTime<FixedOffset>, String)>> {
+    fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            let invalid = |why: String| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}:{}: {}", path.display(), i + 1, why),
+                )
+            };
+            let (time, message) = line
+                .split_once('\t')
+                .ok_or_else(|| invalid("expected a time, a tab and a message".to_string()))?;
+            let time = DateTime::parse_from_rfc3339(time).map_err(|e| invalid(e.to_string()))?;
+            Ok((time, unescape(message)))
+        })
+        .collect()
+}
+
+fn escape(message: &str) -> String {
+    message
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape(message: &str) -> String {
+    let mut unescaped = String::with_capacity(message.len());
+    let mut chars = message.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
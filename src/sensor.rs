@@ -0,0 +1,120 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use sysinfo::Components;
+
+use crate::{
+    cli::CpuSensor,
+    workers::{self, Workers},
+};
+
+/// How often LibreHardwareMonitor is asked while it answers.
+const LHM_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often LibreHardwareMonitor is looked for again while it doesn't.
+const LHM_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Reads the cpu temperature in °C, from LibreHardwareMonitor if it is
+/// running and wanted, otherwise from sysinfo's components.
+pub struct CpuTemperature {
+    /// The latest LibreHardwareMonitor reading, kept by a worker
+    lhm: Option<Arc<Mutex<Option<f32>>>>,
+    components: Components,
+}
+
+impl CpuTemperature {
+    pub fn new(sensor: CpuSensor, workers: &mut Workers) -> Self {
+        let use_lhm = match sensor {
+            CpuSensor::Lhm if !cfg!(windows) => {
+                eprintln!("LibreHardwareMonitor is only supported on Windows, using sysinfo");
+                false
+            }
+            CpuSensor::Lhm => true,
+            CpuSensor::Sysinfo => false,
+            // the worker falls back to sysinfo until LibreHardwareMonitor answers
+            CpuSensor::Auto => cfg!(windows),
+        };
+        Self {
+            lhm: use_lhm.then(|| watch_lhm(workers)),
+            components: Components::new_with_refreshed_list(),
+        }
+    }
+
+    pub fn read(&mut self) -> Option<f32> {
+        // fall back to sysinfo while LibreHardwareMonitor isn't running
+        if let Some(temp) = self.lhm.as_ref().and_then(|latest| *latest.lock().unwrap()) {
+            return Some(temp);
+        }
+        self.components.refresh();
+        self.components
+            .iter()
+            .filter(|component| {
+                let label = component.label().to_lowercase();
+                ["cpu", "package", "tctl", "tdie", "coretemp", "k10temp"]
+                    .iter()
+                    .any(|name| label.contains(name))
+            })
+            .map(|component| component.temperature())
+            .filter(|temp| temp.is_finite() && *temp > 0.0)
+            .reduce(f32::max)
+    }
+}
+
+/// Asks LibreHardwareMonitor in a worker, as each query takes a while, and
+/// less often while it isn't running.
+fn watch_lhm(workers: &mut Workers) -> Arc<Mutex<Option<f32>>> {
+    let latest = Arc::new(Mutex::new(None));
+    let shared = Arc::clone(&latest);
+    let running = workers.running();
+    workers.spawn("librehardwaremonitor", move || loop {
+        let temp = lhm::read();
+        *shared.lock().unwrap() = temp;
+        let interval = if temp.is_some() {
+            LHM_POLL_INTERVAL
+        } else {
+            LHM_RETRY_INTERVAL
+        };
+        if !workers::sleep_while(&running, interval) {
+            break;
+        }
+    });
+    latest
+}
+
+#[cfg(windows)]
+mod lhm {
+    use std::{os::windows::process::CommandExt, process::Command};
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    const QUERY: &str = "Get-CimInstance -Namespace root/LibreHardwareMonitor -ClassName Sensor \
+        -Filter \"SensorType='Temperature'\" -ErrorAction SilentlyContinue \
+        | Where-Object { $_.Name -like 'CPU Package*' -or $_.Name -like 'Core (Tctl*' } \
+        | Select-Object -First 1 -ExpandProperty Value";
+
+    /// Asks LibreHardwareMonitor's WMI provider for the cpu package temperature.
+    pub fn read() -> Option<f32> {
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", QUERY])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .replace(',', ".")
+            .parse()
+            .ok()
+    }
+}
+
+#[cfg(not(windows))]
+mod lhm {
+    pub fn read() -> Option<f32> {
+        None
+    }
+}
@@ -0,0 +1,329 @@
+use std::{env, io, path::Path};
+
+use clap::{CommandFactory, ValueHint};
+
+use crate::cli::Args;
+
+#[cfg(target_os = "linux")]
+use std::{fs, path::PathBuf};
+
+#[cfg(any(target_os = "linux", windows))]
+use std::process::Command;
+
+/// The name of the startup entry.
+#[cfg(any(target_os = "linux", windows))]
+const NAME: &str = "sysinfo-osc-client-vrc";
+
+/// The options to start with: this program's own, without the subcommand
+/// and with their paths made absolute, since the service starts elsewhere.
+pub fn baked_args(subcommand: &str) -> io::Result<Vec<String>> {
+    Ok(bake(
+        &Args::command(),
+        env::args().skip(1),
+        subcommand,
+        &env::current_dir()?,
+    ))
+}
+
+fn bake(
+    command: &clap::Command,
+    args: impl IntoIterator<Item = String>,
+    subcommand: &str,
+    dir: &Path,
+) -> Vec<String> {
+    let absolute = |option: &clap::Arg, value: String| match option.get_value_hint() {
+        ValueHint::AnyPath if Path::new(&value).is_relative() => {
+            dir.join(value).display().to_string()
+        }
+        _ => value,
+    };
+    let mut baked = Vec::new();
+    let mut found = false;
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            baked.push(arg);
+            baked.extend(args);
+            break;
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            // the first word that isn't an option's value is the subcommand
+            if !found && arg == subcommand {
+                found = true;
+            } else {
+                baked.push(arg);
+            }
+            continue;
+        }
+        match value_option(command, &arg) {
+            Some((option, Some(start))) => baked.push(format!(
+                "{}{}",
+                &arg[..start],
+                absolute(option, arg[start..].to_string())
+            )),
+            Some((option, None)) => {
+                baked.push(arg);
+                let optional = option
+                    .get_num_args()
+                    .is_some_and(|range| range.min_values() == 0);
+                if let Some(value) = args.next_if(|next| !optional || !next.starts_with('-')) {
+                    baked.push(absolute(option, value));
+                }
+            }
+            None => baked.push(arg),
+        }
+    }
+    baked
+}
+
+/// The option in `arg` that takes a value, with where the value starts if it
+/// is part of `arg` too, as in `--config=a.conf` or `-i5`.
+fn value_option<'a>(
+    command: &'a clap::Command,
+    arg: &str,
+) -> Option<(&'a clap::Arg, Option<usize>)> {
+    if let Some(long) = arg.strip_prefix("--") {
+        let name = long.split_once('=').map_or(long, |(name, _)| name);
+        let option = command
+            .get_arguments()
+            .find(|option| option.get_long() == Some(name))?;
+        let start = long.contains('=').then_some(2 + name.len() + 1);
+        return option
+            .get_action()
+            .takes_values()
+            .then_some((option, start));
+    }
+    // `-tc` is two flags, while the first short option with a value takes
+    // the rest of the word
+    for (i, short) in arg.char_indices().skip(1) {
+        let option = command
+            .get_arguments()
+            .find(|option| option.get_short() == Some(short))?;
+        if option.get_action().takes_values() {
+            let start = i + short.len_utf8();
+            let start = start + usize::from(arg[start..].starts_with('='));
+            return Some((option, (start < arg.len()).then_some(start)));
+        }
+    }
+    None
+}
+
+#[cfg(any(target_os = "linux", windows))]
+fn run(program: &str, args: &[&str]) -> io::Result<()> {
+    println!("running: {} {}", program, args.join(" "));
+    let status = Command::new(program).args(args).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} failed: {}", program, status)));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn unit_path() -> io::Result<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cannot find the home directory"))?;
+    Ok(config
+        .join("systemd/user")
+        .join(format!("{}.service", NAME)))
+}
+
+/// Quotes `arg` for a systemd `ExecStart=` line, where `%` and `$` would
+/// otherwise be expanded, e.g. in `--uptime-format %h:%M`.
+#[cfg(target_os = "linux")]
+fn quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+/// Starts this program with `args` at login, as a systemd user unit.
+#[cfg(target_os = "linux")]
+pub fn install(args: &[String]) -> io::Result<()> {
+    let exe = env::current_exe()?;
+    let exec: Vec<String> = std::iter::once(exe.display().to_string())
+        .chain(args.iter().cloned())
+        .map(|arg| quote(&arg))
+        .collect();
+    let unit = format!(
+        "[Unit]\n\
+         Description=Send system information to VRChat over OSC\n\
+         \n\
+         [Service]\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        exec.join(" ")
+    );
+    let path = unit_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, unit)?;
+    println!("created {}", path.display());
+    run("systemctl", &["--user", "daemon-reload"])?;
+    run(
+        "systemctl",
+        &["--user", "enable", "--now", &format!("{}.service", NAME)],
+    )
+}
+
+#[cfg(target_os = "linux")]
+pub fn uninstall() -> io::Result<()> {
+    let path = unit_path()?;
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not installed", path.display()),
+        ));
+    }
+    run(
+        "systemctl",
+        &["--user", "disable", "--now", &format!("{}.service", NAME)],
+    )?;
+    fs::remove_file(&path)?;
+    println!("removed {}", path.display());
+    run("systemctl", &["--user", "daemon-reload"])
+}
+
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+/// Starts this program with `args` at login, from the registry's Run key.
+#[cfg(windows)]
+pub fn install(args: &[String]) -> io::Result<()> {
+    let exe = env::current_exe()?;
+    let quote = |arg: &str| {
+        if arg.contains([' ', '\t', '"']) {
+            format!("\"{}\"", arg.replace('"', "\\\""))
+        } else {
+            arg.to_string()
+        }
+    };
+    let command: Vec<String> = std::iter::once(format!("\"{}\"", exe.display()))
+        .chain(args.iter().map(|arg| quote(arg)))
+        .collect();
+    run(
+        "reg",
+        &[
+            "add",
+            RUN_KEY,
+            "/v",
+            NAME,
+            "/t",
+            "REG_SZ",
+            "/d",
+            &command.join(" "),
+            "/f",
+        ],
+    )?;
+    println!("created {}\\{}", RUN_KEY, NAME);
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn uninstall() -> io::Result<()> {
+    run("reg", &["delete", RUN_KEY, "/v", NAME, "/f"])?;
+    println!("removed {}\\{}", RUN_KEY, NAME);
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn install(_args: &[String]) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "install is only supported on Linux and Windows",
+    ))
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn uninstall() -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "uninstall is only supported on Linux and Windows",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::Command;
+
+    fn bake_install(args: &[&str]) -> Vec<String> {
+        let parsed =
+            Args::parse_from(std::iter::once("sysinfo-osc-client-vrc").chain(args.iter().copied()));
+        assert_eq!(parsed.command, Some(Command::Install));
+        let baked = bake(
+            &Args::command(),
+            args.iter().map(|arg| arg.to_string()),
+            "install",
+            Path::new("/home/user"),
+        );
+        let parsed = Args::parse_from(
+            std::iter::once("sysinfo-osc-client-vrc".to_string()).chain(baked.iter().cloned()),
+        );
+        assert_eq!(parsed.command, None);
+        baked
+    }
+
+    #[test]
+    fn only_the_subcommand_is_removed() {
+        assert_eq!(
+            bake_install(&[
+                "--quips",
+                "install",
+                "-w",
+                "install",
+                "-ti",
+                "5",
+                "install",
+                "--single-line",
+                "--compact"
+            ]),
+            [
+                "--quips",
+                "install",
+                "-w",
+                "install",
+                "-ti",
+                "5",
+                "--single-line",
+                "--compact"
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_are_made_absolute() {
+        assert_eq!(
+            bake_install(&[
+                "install",
+                "--config",
+                "vrc.conf",
+                "--record=log.txt",
+                "--plugin-dir",
+                "/opt/plugins",
+                "--quips",
+                "a/b"
+            ]),
+            [
+                "--config",
+                "/home/user/vrc.conf",
+                "--record=/home/user/log.txt",
+                "--plugin-dir",
+                "/opt/plugins",
+                "--quips",
+                "a/b"
+            ]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    cell::{Cell, RefCell},
+    io::{self, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use std::{
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+};
+
+use crate::workers;
+
+/// The port VRChat sends its own OSC messages to.
+pub const OSC_LISTEN_PORT: u16 = 9001;
+
+/// How long connecting to or writing to a tcp OSC server may take.
+const TCP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// SLIP frame delimiter and escapes, RFC 1055.
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+/// Where encoded OSC packets are sent, at most one every `min_gap`.
+pub struct Transport {
+    link: Link,
+    min_gap: Duration,
+    last_send: Cell<Option<Instant>>,
+    /// Sends that had to wait for `min_gap`
+    throttled: Cell<u64>,
+    /// Cut the wait for `min_gap` short on shutdown
+    running: Arc<AtomicBool>,
+}
+
+enum Link {
+    Udp {
+        socket: UdpSocket,
+        target: SocketAddr,
+        connected: bool,
+    },
+    /// OSC 1.1 over tcp, one SLIP frame per packet. The connection is opened
+    /// on the first send and again on the next send after it drops.
+    Tcp {
+        stream: RefCell<Option<TcpStream>>,
+        target: SocketAddr,
+    },
+    #[cfg(unix)]
+    Unix { socket: UnixDatagram, path: PathBuf },
+}
+
+impl Transport {
+    /// Sends to an OSC server over udp. A `connected` socket is told about
+    /// port unreachable replies, so later sends fail with `ConnectionRefused`.
+    /// A `source` address must belong to one of this machine's interfaces.
+    pub fn udp(host: &str, port: u16, connected: bool, source: Option<IpAddr>) -> io::Result<Self> {
+        let target = (host, port).to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("cannot resolve {}", host))
+        })?;
+        let socket = match (target, source) {
+            (_, Some(source)) if source.is_ipv4() != target.is_ipv4() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("cannot send from {} to {}", source, target),
+                ))
+            }
+            (_, Some(source)) => UdpSocket::bind((source, 0)).map_err(|e| match e.kind() {
+                io::ErrorKind::AddrNotAvailable => io::Error::new(
+                    e.kind(),
+                    format!("{} is not an address of this machine", source),
+                ),
+                _ => e,
+            })?,
+            // a connected socket would filter out what VRChat sends to its
+            // port, so it leaves the port to `listen_socket`
+            (SocketAddr::V4(addr), None) if addr.ip().is_loopback() && !connected => {
+                UdpSocket::bind((Ipv4Addr::LOCALHOST, OSC_LISTEN_PORT))?
+            }
+            (SocketAddr::V4(addr), None) if addr.ip().is_loopback() => {
+                UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?
+            }
+            (SocketAddr::V4(_), None) => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?,
+            (SocketAddr::V6(_), None) => UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0))?,
+        };
+        if connected {
+            socket.connect(target)?;
+        }
+        Ok(Link::Udp {
+            socket,
+            target,
+            connected,
+        }
+        .into())
+    }
+
+    /// Sends to an OSC server over tcp.
+    pub fn tcp(host: &str, port: u16) -> io::Result<Self> {
+        let target = (host, port).to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("cannot resolve {}", host))
+        })?;
+        Ok(Link::Tcp {
+            stream: RefCell::new(None),
+            target,
+        }
+        .into())
+    }
+
+    /// Sends to a local relay listening on a unix datagram socket.
+    #[cfg(unix)]
+    pub fn unix(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("unix socket {} does not exist", path.display()),
+            ));
+        }
+        Ok(Link::Unix {
+            socket: UnixDatagram::unbound()?,
+            path: path.to_path_buf(),
+        }
+        .into())
+    }
+
+    /// Waits until `min_gap` passed since the last send before each send,
+    /// unless `running` is cleared meanwhile.
+    pub fn with_min_gap(mut self, min_gap: Duration, running: Arc<AtomicBool>) -> Self {
+        self.min_gap = min_gap;
+        self.running = running;
+        self
+    }
+
+    /// How many sends were held back by `min_gap` so far.
+    pub fn throttled(&self) -> u64 {
+        self.throttled.get()
+    }
+
+    /// A socket for reading what the OSC server sends back. An unconnected
+    /// udp socket to a local server already owns the port and is shared;
+    /// otherwise, including a connected one, which sends from a port of its
+    /// own, the port is bound here.
+    pub fn listen_socket(&self) -> io::Result<UdpSocket> {
+        match &self.link {
+            Link::Udp {
+                socket,
+                connected: false,
+                ..
+            } if socket.local_addr()?.port() == OSC_LISTEN_PORT => socket.try_clone(),
+            _ => UdpSocket::bind((Ipv4Addr::LOCALHOST, OSC_LISTEN_PORT)),
+        }
+    }
+
+    /// Every message goes out through here, so this is where sends that come
+    /// faster than `min_gap` are held back.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(last) = self.last_send.get() {
+            let now = Instant::now();
+            if last + self.min_gap > now {
+                self.throttled.set(self.throttled.get() + 1);
+                // on shutdown the last message goes out right away
+                workers::sleep_while(&self.running, last + self.min_gap - now);
+            }
+        }
+        let result = self.link.send(buf);
+        self.last_send.set(Some(Instant::now()));
+        result
+    }
+}
+
+impl From<Link> for Transport {
+    fn from(link: Link) -> Self {
+        Self {
+            link,
+            min_gap: Duration::ZERO,
+            last_send: Cell::new(None),
+            throttled: Cell::new(0),
+            running: Arc::new(AtomicBool::new(true)),
+        }
+    }
+}
+
+impl Link {
+    fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Udp {
+                socket,
+                connected: true,
+                ..
+            } => socket.send(buf),
+            Self::Udp { socket, target, .. } => socket.send_to(buf, target),
+            Self::Tcp { stream, target } => {
+                let mut stream = stream.borrow_mut();
+                // a failed write leaves the connection out, to reconnect next time
+                let mut connection = match stream.take() {
+                    Some(connection) => connection,
+                    None => {
+                        let connection = TcpStream::connect_timeout(target, TCP_TIMEOUT)?;
+                        connection.set_nodelay(true)?;
+                        connection.set_write_timeout(Some(TCP_TIMEOUT))?;
+                        connection
+                    }
+                };
+                connection.write_all(&slip_encode(buf))?;
+                *stream = Some(connection);
+                Ok(buf.len())
+            }
+            #[cfg(unix)]
+            Self::Unix { socket, path } => socket.send_to(buf, path),
+        }
+    }
+}
+
+impl std::fmt::Display for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.link {
+            Link::Udp { target, .. } => write!(f, "udp {}", target),
+            Link::Tcp { target, .. } => write!(f, "tcp {}", target),
+            #[cfg(unix)]
+            Link::Unix { path, .. } => write!(f, "unix {}", path.display()),
+        }
+    }
+}
+
+/// Frames a packet with SLIP, with an `END` on both sides as OSC 1.1 does.
+fn slip_encode(packet: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(packet.len() + 2);
+    frame.push(SLIP_END);
+    for &byte in packet {
+        match byte {
+            SLIP_END => frame.extend([SLIP_ESC, SLIP_ESC_END]),
+            SLIP_ESC => frame.extend([SLIP_ESC, SLIP_ESC_ESC]),
+            byte => frame.push(byte),
+        }
+    }
+    frame.push(SLIP_END);
+    frame
+}
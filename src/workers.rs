@@ -0,0 +1,67 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Side threads that run next to the main loop until `running` is cleared.
+pub struct Workers {
+    running: Arc<AtomicBool>,
+    handles: Vec<(String, JoinHandle<()>)>,
+}
+
+impl Workers {
+    pub fn new(running: Arc<AtomicBool>) -> Self {
+        Self {
+            running,
+            handles: Vec::new(),
+        }
+    }
+
+    /// The flag every worker should check to know when to stop.
+    pub fn running(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.running)
+    }
+
+    pub fn spawn<F: FnOnce() + Send + 'static>(&mut self, name: &str, f: F) {
+        match thread::Builder::new().name(name.to_string()).spawn(f) {
+            Ok(handle) => self.handles.push((name.to_string(), handle)),
+            Err(e) => eprintln!("cannot start {}: {}", name, e),
+        }
+    }
+
+    /// Tells every worker to stop and waits up to `timeout` for all of them.
+    pub fn shutdown(self, timeout: Duration) {
+        self.running.store(false, Ordering::SeqCst);
+        let deadline = Instant::now() + timeout;
+        for (name, handle) in self.handles {
+            while !handle.is_finished() && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(20));
+            }
+            if handle.is_finished() {
+                if handle.join().is_err() {
+                    eprintln!("{} panicked", name);
+                }
+            } else {
+                eprintln!("{} did not stop in time", name);
+            }
+        }
+    }
+}
+
+/// Sleeps for `duration`, waking up early if `running` is cleared.
+/// Returns whether it is still running.
+pub fn sleep_while(running: &AtomicBool, duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while running.load(Ordering::SeqCst) {
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        thread::sleep((deadline - now).min(Duration::from_millis(100)));
+    }
+    false
+}